@@ -6,19 +6,245 @@
 // License: GPLv2
 // ============================================================================
 
+use std::cell::{Cell, RefCell};
+use std::fmt::Write as _;
 use std::sync::mpsc::{Receiver, Sender};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use chrono::Local;
+use chrono::{DateTime, Local};
 use log::{info, error};
-use enigo::{Enigo, Button, Direction, Coordinate, Settings, Keyboard, Mouse};
+use enigo::{Enigo, Button, Direction, Coordinate, Key, Settings, Keyboard, Mouse};
+use serde::Serialize;
+use sysinfo::System;
 
-use crate::config::ClickPoint;
+use crate::config::{ClickButton, ClickKind, ClickPoint, StepAction};
+use crate::paths;
+use crate::screenshot::{ScreenSample, ScreenshotManager};
+use crate::telemetry::Telemetry;
+use crate::util::SimpleRng;
 use crate::watchdog::WatchdogTimer;
 
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+/// Fraction of sampled pixels that must change to count as a UI update
+const CALIBRATION_CHANGE_THRESHOLD: f64 = 0.005;
+const CALIBRATION_POLL_MS: u64 = 250;
+const CALIBRATION_TIMEOUT_SEC: u64 = 60;
+const CALIBRATION_REPORT_FILE: &str = "calibration_report.json";
+
+/// Pause after the focus click before the first step
+const FOCUS_SETTLE: Duration = Duration::from_millis(500);
+/// Largest random offset (each axis) applied to clicks in humanize mode
+const HUMANIZE_JITTER_PX: i32 = 3;
+/// Post-click delays vary by up to this fraction in humanize mode
+const HUMANIZE_DELAY_FRACTION: f64 = 0.2;
+/// Pause between the two clicks of a double-click (well inside OS double-click time)
+const DOUBLE_CLICK_GAP: Duration = Duration::from_millis(80);
+
+/// Points used by the classic choreography (points without actions)
+const CLASSIC_STEP_COUNT: usize = 6;
+
+/// Default spacing of progress log entries during the long wait
+const DEFAULT_WAIT_MILESTONE_SEC: i32 = 15 * 60;
+
+/// How long a click aimed at our own window waits for the window to move
+const SELF_CLICK_WAIT_SEC: u64 = 10;
+
+/// Pause before retry N of a failed click is N times this
+const RETRY_BACKOFF_STEP: Duration = Duration::from_millis(500);
+/// Pause before retrying when the input backend couldn't be created, which is
+/// usually a transient system state (secure desktop, session switch)
+const ENIGO_RETRY_BACKOFF: Duration = Duration::from_secs(2);
+/// Prefix of errors caused by the input backend failing to initialise
+const ENIGO_FAILURE: &str = "Enigo creation failed";
+
+/// Date format used by `{date}` when no format is given
+pub const DEFAULT_DATE_FORMAT: &str = "%d-%m-%Y";
+
+/// Text typed into the Step 2 field unless overridden
+pub const DEFAULT_TEXT_TEMPLATE: &str = "{date}";
+
+// ============================================================================
+// DATE FORMATTING
+// ============================================================================
+
+/// Format `now` with a chrono strftime string.
+///
+/// Pure (no clock access) so callers decide which instant is typed.
+/// Returns an empty string if `fmt` contains an invalid specifier.
+pub fn format_date(now: DateTime<Local>, fmt: &str) -> String {
+    let mut out = String::new();
+    match write!(out, "{}", now.format(fmt)) {
+        Ok(()) => out,
+        Err(_) => String::new(),
+    }
+}
+
+/// Check that `fmt` is a usable strftime string (valid and not blank)
+pub fn validate_date_format(fmt: &str) -> Result<(), String> {
+    if format_date(Local::now(), fmt).trim().is_empty() {
+        return Err(format!("Invalid date format '{}' (see chrono strftime specifiers)", fmt));
+    }
+    Ok(())
+}
+
+// ============================================================================
+// TEXT TEMPLATES
+// ============================================================================
+
+/// Expand placeholders in a step's text template.
+///
+/// Supported placeholders:
+/// - `{date}` / `{date:FORMAT}` - current date (chrono strftime, default `%d-%m-%Y`)
+/// - `{time}` / `{time:FORMAT}` - current time (default `%H:%M:%S`)
+/// - `{iteration}` - current iteration number (1-based)
+/// - `{env:NAME}` - value of environment variable `NAME` (empty if unset)
+/// - `{{` / `}}` - literal braces
+///
+/// Unknown placeholders and invalid formats are left in the output unchanged.
+pub fn render_template(template: &str, now: DateTime<Local>, iteration: u32) -> String {
+    render_template_with(template, now, iteration, DEFAULT_DATE_FORMAT)
+}
+
+/// Like [`render_template`], with `date_format` used for a bare `{date}`
+pub fn render_template_with(template: &str, now: DateTime<Local>, iteration: u32, date_format: &str) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(pos) = rest.find(['{', '}']) {
+        out.push_str(&rest[..pos]);
+        let tail = &rest[pos..];
+
+        if tail.starts_with("{{") || tail.starts_with("}}") {
+            out.push_str(&tail[..1]);
+            rest = &tail[2..];
+            continue;
+        }
+
+        let end = match (tail.starts_with('{'), tail.find('}')) {
+            (true, Some(end)) => end,
+            _ => {
+                out.push_str(&tail[..1]);
+                rest = &tail[1..];
+                continue;
+            }
+        };
+
+        let placeholder = &tail[1..end];
+        let (key, arg) = match placeholder.split_once(':') {
+            Some((key, arg)) => (key, Some(arg)),
+            None => (placeholder, None),
+        };
+
+        let mut value = String::new();
+        let expanded = match key {
+            "date" => {
+                value = format_date(now, arg.unwrap_or(date_format));
+                !value.is_empty()
+            }
+            "time" => {
+                value = format_date(now, arg.unwrap_or("%H:%M:%S"));
+                !value.is_empty()
+            }
+            "iteration" if arg.is_none() => write!(value, "{}", iteration).is_ok(),
+            "env" => match arg {
+                Some(name) => {
+                    value = std::env::var(name).unwrap_or_default();
+                    true
+                }
+                None => false,
+            },
+            _ => false,
+        };
+
+        if expanded {
+            out.push_str(&value);
+        } else {
+            out.push_str(&tail[..=end]);
+        }
+        rest = &tail[end + 1..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+// ============================================================================
+// WATCHDOG
+// ============================================================================
+
+/// Seconds a single click step may take before the watchdog stops automation.
+/// Configured waits (step delay, step 4 and long wait) run with it cancelled.
+pub fn watchdog_timeout_sec(max_retries: i32) -> u64 {
+    (max_retries.max(1) as u64 * 3).max(30)
+}
+
+// ============================================================================
+// INPUT
+// ============================================================================
+
+/// Move the mouse to `point` and left-click once
+pub fn click_at(point: &ClickPoint) -> Result<(), String> {
+    let mut enigo = Enigo::new(&Settings::default())
+        .map_err(|e| format!("{}: {}", ENIGO_FAILURE, e))?;
+    
+    // Move mouse
+    enigo.move_mouse(point.x, point.y, Coordinate::Abs)
+        .map_err(|e| format!("Mouse move failed: {}", e))?;
+    thread::sleep(Duration::from_millis(50));
+    
+    let button = match point.button {
+        ClickButton::Left => Button::Left,
+        ClickButton::Right => Button::Right,
+        ClickButton::Middle => Button::Middle,
+    };
+    let clicks = match point.click_kind {
+        ClickKind::Single => 1,
+        ClickKind::Double => 2,
+    };
+
+    // Click
+    for i in 0..clicks {
+        if i > 0 {
+            thread::sleep(DOUBLE_CLICK_GAP);
+        }
+        enigo.button(button, Direction::Click)
+            .map_err(|e| format!("Mouse click failed: {}", e))?;
+    }
+    Ok(())
+}
+
+// ============================================================================
+// WINDOW BOUNDS
+// ============================================================================
+
+/// Screen rectangle in physical pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScreenRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl ScreenRect {
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Self { x, y, width, height }
+    }
+
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+}
+
+/// Bounds of the app's own window, kept current by the GUI (None = hidden/unknown)
+pub type SharedWindowBounds = Arc<Mutex<Option<ScreenRect>>>;
+
 // ============================================================================
 // AUTOMATION MESSAGES
 // ============================================================================
@@ -30,9 +256,34 @@ pub enum AutomationMessage {
     Status(String),
     UpdateTimer(i32),
     ErrorPopup(String),
+    Calibration(CalibrationReport),
     Stop,
 }
 
+// ============================================================================
+// CALIBRATION REPORT
+// ============================================================================
+
+/// Measured reaction time for a single step
+#[derive(Debug, Clone, Serialize)]
+pub struct StepTiming {
+    pub name: String,
+    /// Time from click to detected screen change (None = no change before timeout)
+    pub measured_ms: Option<u64>,
+    pub suggested_delay_sec: i32,
+    /// No screen change was seen; the suggestion is the current step delay and
+    /// the step is left out of the report's suggested step delay
+    pub timed_out: bool,
+}
+
+/// Result of a calibration run with suggested timing values
+#[derive(Debug, Clone, Serialize)]
+pub struct CalibrationReport {
+    pub steps: Vec<StepTiming>,
+    pub suggested_step_delay: i32,
+    pub suggested_step4_wait: i32,
+}
+
 // ============================================================================
 // AUTOMATION THREAD
 // ============================================================================
@@ -44,10 +295,34 @@ pub struct AutomationThread {
     step_delay: i32,
     max_retries: i32,
     step4_wait_sec: i32,
+    max_iterations: Option<u32>,
     dry_run: bool,
     tx_to_gui: Sender<AutomationMessage>,
     rx_stop: Receiver<()>,
     stop_flag: Arc<AtomicBool>,
+    idle_cpu_threshold: Option<f32>,
+    idle_cpu_timeout_sec: i32,
+    screenshots: Option<Arc<ScreenshotManager>>,
+    telemetry: Option<Arc<Telemetry>>,
+    calibrate: bool,
+    text_template: String,
+    date_format: String,
+    verify_text: bool,
+    single_pass: bool,
+    min_click_interval: Option<Duration>,
+    last_click: Cell<Option<Instant>>,
+    error_clip_sec: u32,
+    start_delay_sec: i32,
+    self_window: Option<SharedWindowBounds>,
+    capture_each_step: bool,
+    failure_captured: Cell<bool>,
+    replay_guard: Option<(Duration, i32)>,
+    last_click_pos: Cell<Option<(i32, i32)>>,
+    wait_milestone_sec: i32,
+    focus_point: Option<ClickPoint>,
+    screenshot_settle: Duration,
+    humanize: Option<RefCell<SimpleRng>>,
+    pause_flag: Option<Arc<AtomicBool>>,
 }
 
 impl AutomationThread {
@@ -57,6 +332,8 @@ impl AutomationThread {
         step_delay: i32,
         max_retries: i32,
         step4_wait_sec: i32,
+        date_format: impl Into<String>,
+        max_iterations: Option<u32>,
         dry_run: bool,
         tx_to_gui: Sender<AutomationMessage>,
         rx_stop: Receiver<()>,
@@ -68,12 +345,153 @@ impl AutomationThread {
             step_delay: step_delay.max(0),
             max_retries: max_retries.max(1),
             step4_wait_sec: step4_wait_sec.max(0),
+            max_iterations: max_iterations.filter(|&n| n > 0),
             dry_run,
             tx_to_gui,
             rx_stop,
             stop_flag,
+            idle_cpu_threshold: None,
+            idle_cpu_timeout_sec: 0,
+            screenshots: None,
+            telemetry: None,
+            calibrate: false,
+            text_template: DEFAULT_TEXT_TEMPLATE.to_string(),
+            date_format: date_format.into(),
+            verify_text: false,
+            single_pass: false,
+            min_click_interval: None,
+            last_click: Cell::new(None),
+            error_clip_sec: 0,
+            start_delay_sec: 0,
+            self_window: None,
+            capture_each_step: false,
+            failure_captured: Cell::new(false),
+            replay_guard: None,
+            last_click_pos: Cell::new(None),
+            wait_milestone_sec: DEFAULT_WAIT_MILESTONE_SEC,
+            focus_point: None,
+            screenshot_settle: Duration::ZERO,
+            humanize: None,
+            pause_flag: None,
         }
     }
+
+    /// Click `point` at the start of every iteration so the target window has
+    /// keyboard focus before the first step
+    pub fn with_focus_point(mut self, point: ClickPoint) -> Self {
+        self.focus_point = Some(point);
+        self
+    }
+
+    /// Log progress every `interval_sec` during the long wait so the activity
+    /// log shows the tool is alive (0 = only the start/end entries)
+    pub fn with_wait_milestones(mut self, interval_sec: i32) -> Self {
+        self.wait_milestone_sec = interval_sec.max(0);
+        self
+    }
+
+    /// Enforce at least `min_interval_ms` between two consecutive clicks within
+    /// `radius_px` of each other, as a safety net against rapid repeat activation
+    pub fn with_replay_guard(mut self, min_interval_ms: u64, radius_px: i32) -> Self {
+        self.replay_guard = (min_interval_ms > 0)
+            .then(|| (Duration::from_millis(min_interval_ms), radius_px.max(0)));
+        self
+    }
+
+    /// Screenshot after every successful click (`<name>_after`), not just on failure
+    pub fn with_step_screenshots(mut self, enabled: bool) -> Self {
+        self.capture_each_step = enabled;
+        self
+    }
+
+    /// While `flag` is set, the run holds between seconds of its waits and
+    /// between steps without advancing timers; clearing it resumes
+    pub fn with_pause_flag(mut self, flag: Arc<AtomicBool>) -> Self {
+        self.pause_flag = Some(flag);
+        self
+    }
+
+    /// Offset each click by up to ±3 px and vary post-click delays by ±20%.
+    /// Pass a seed for a reproducible sequence of offsets and delays.
+    pub fn with_humanize(mut self, seed: Option<u64>) -> Self {
+        let rng = seed.map_or_else(SimpleRng::from_clock, SimpleRng::new);
+        self.humanize = Some(RefCell::new(rng));
+        self
+    }
+
+    /// Pause `ms` before step and failure screenshots so the target UI has
+    /// finished redrawing. Independent of the step delay.
+    pub fn with_screenshot_settle(mut self, ms: u64) -> Self {
+        self.screenshot_settle = Duration::from_millis(ms);
+        self
+    }
+
+    /// Refuse to click inside the app's own window. A click that would land on
+    /// it waits briefly for the window to move, then fails the attempt.
+    pub fn with_self_click_guard(mut self, bounds: SharedWindowBounds) -> Self {
+        self.self_window = Some(bounds);
+        self
+    }
+
+    /// Count down `seconds` in the status before the first click, giving the
+    /// user time to focus the target window
+    pub fn with_start_delay(mut self, seconds: i32) -> Self {
+        self.start_delay_sec = seconds.max(0);
+        self
+    }
+
+    /// On an automation error, record `seconds` of the screen next to the
+    /// failure screenshot (needs the screenshots and video features)
+    pub fn with_error_clip(mut self, seconds: u32) -> Self {
+        self.error_clip_sec = seconds;
+        self
+    }
+
+    /// Cap the click rate regardless of configured delays (0 = unlimited)
+    pub fn with_max_clicks_per_minute(mut self, max: u32) -> Self {
+        self.min_click_interval = (max > 0).then(|| Duration::from_secs_f64(60.0 / max as f64));
+        self
+    }
+
+    /// After typing, copy the field back via the clipboard and retry the
+    /// entry (up to `max_retries`) if it doesn't match what was typed
+    pub fn with_text_verification(mut self, verify: bool) -> Self {
+        self.verify_text = verify;
+        self
+    }
+
+    /// Text typed after the Step 2 click; see [`render_template`] for placeholders
+    pub fn with_text_template(mut self, template: impl Into<String>) -> Self {
+        self.text_template = template.into();
+        self
+    }
+
+    /// Attach the screenshot manager used for screen sampling and captures
+    pub fn with_screenshots(mut self, screenshots: Arc<ScreenshotManager>) -> Self {
+        self.screenshots = Some(screenshots);
+        self
+    }
+
+    /// Count clicks, errors and iterations in `telemetry`'s session summary
+    pub fn with_telemetry(mut self, telemetry: Arc<Telemetry>) -> Self {
+        self.telemetry = Some(telemetry);
+        self
+    }
+
+    /// Run the sequence once in calibration mode, measuring how long each
+    /// step takes to visibly update the screen instead of looping
+    pub fn with_calibration(mut self) -> Self {
+        self.calibrate = true;
+        self
+    }
+
+    /// After each click, wait until system CPU usage drops below
+    /// `threshold_percent` (or `timeout_sec` elapses) before continuing
+    pub fn with_idle_cpu_gate(mut self, threshold_percent: f32, timeout_sec: i32) -> Self {
+        self.idle_cpu_threshold = Some(threshold_percent.clamp(1.0, 100.0));
+        self.idle_cpu_timeout_sec = timeout_sec.max(1);
+        self
+    }
     
     fn log(&self, msg: impl AsRef<str>) {
         let msg = msg.as_ref();
@@ -81,6 +499,13 @@ impl AutomationThread {
         let _ = self.tx_to_gui.send(AutomationMessage::Log(msg.to_string()));
     }
     
+    /// Bump a telemetry session counter (no-op without telemetry)
+    fn count(&self, key: &str) {
+        if let Some(telemetry) = &self.telemetry {
+            telemetry.increment(key);
+        }
+    }
+    
     fn update_status(&self, status: impl AsRef<str>) {
         let _ = self.tx_to_gui.send(AutomationMessage::Status(status.as_ref().to_string()));
     }
@@ -89,6 +514,14 @@ impl AutomationThread {
         let _ = self.tx_to_gui.send(AutomationMessage::UpdateTimer(remaining));
     }
     
+    /// Log an error to the file log and the GUI activity log
+    fn log_error(&self, msg: impl AsRef<str>) {
+        let msg = msg.as_ref();
+        error!("{}", msg);
+        self.count("errors");
+        let _ = self.tx_to_gui.send(AutomationMessage::Log(format!("ERROR: {}", msg)));
+    }
+    
     fn error_popup(&self, msg: impl AsRef<str>) {
         let msg = msg.as_ref();
         error!("{}", msg);
@@ -99,9 +532,41 @@ impl AutomationThread {
         !self.stop_flag.load(Ordering::SeqCst) && self.rx_stop.try_recv().is_err()
     }
     
+    fn is_paused(&self) -> bool {
+        self.pause_flag.as_ref().is_some_and(|f| f.load(Ordering::SeqCst))
+    }
+    
+    /// Block while paused; false if stopped meanwhile. `watchdog` (if given)
+    /// is cancelled while paused so a long pause doesn't trip it.
+    fn wait_while_paused(&self, watchdog: Option<&WatchdogTimer>) -> bool {
+        if !self.is_paused() {
+            return true;
+        }
+        
+        if let Some(watchdog) = watchdog {
+            watchdog.cancel();
+        }
+        self.log("Paused");
+        self.update_status("Status: Paused");
+        
+        while self.is_paused() {
+            if !self.is_running() {
+                return false;
+            }
+            thread::sleep(Duration::from_millis(200));
+        }
+        
+        self.log("Resumed");
+        self.update_status("Status: Running");
+        if let Some(watchdog) = watchdog {
+            watchdog.reset();
+        }
+        true
+    }
+    
     fn sleep_with_check(&self, seconds: i32) -> bool {
         for _ in 0..seconds {
-            if !self.is_running() {
+            if !self.wait_while_paused(None) || !self.is_running() {
                 self.log("Interrupted during sleep");
                 return false;
             }
@@ -110,6 +575,37 @@ impl AutomationThread {
         true
     }
     
+    /// Wait for CPU usage to settle below the threshold; false if interrupted
+    fn wait_for_idle_cpu(&self, threshold: f32) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(self.idle_cpu_timeout_sec as u64);
+        let mut sys = System::new();
+        sys.refresh_cpu_usage();
+        
+        loop {
+            thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL.max(Duration::from_millis(250)));
+            if !self.is_running() {
+                self.log("Interrupted while waiting for idle CPU");
+                return false;
+            }
+            
+            sys.refresh_cpu_usage();
+            let usage = sys.global_cpu_usage();
+            
+            if usage < threshold {
+                self.log(format!("CPU idle ({:.0}% < {:.0}%)", usage, threshold));
+                return true;
+            }
+            
+            if Instant::now() >= deadline {
+                self.log(format!(
+                    "CPU still busy ({:.0}%) after {}s, continuing",
+                    usage, self.idle_cpu_timeout_sec
+                ));
+                return true;
+            }
+        }
+    }
+    
     fn execute_click(&self, point: &ClickPoint, watchdog: &WatchdogTimer) -> bool {
         for attempt in 1..=self.max_retries {
             if !self.is_running() {
@@ -118,45 +614,180 @@ impl AutomationThread {
             
             self.log(format!("[{}] Attempt {}/{}", point.name, attempt, self.max_retries));
             
-            if !self.dry_run {
-                match Enigo::new(&Settings::default()) {
-                    Ok(mut enigo) => {
-                        // Move mouse
-                        if let Err(e) = enigo.move_mouse(point.x, point.y, Coordinate::Abs) {
-                            error!("Mouse move failed: {}", e);
-                            continue;
-                        }
-                        thread::sleep(Duration::from_millis(50));
-                        
-                        // Click
-                        if let Err(e) = enigo.button(Button::Left, Direction::Click) {
-                            error!("Mouse click failed: {}", e);
-                            continue;
-                        }
-                    }
-                    Err(e) => {
-                        error!("Enigo creation failed: {}", e);
-                        continue;
-                    }
+            // A retry repeats the failed click on purpose, so only the first
+            // attempt is checked against the replay guard
+            let guarded = if attempt == 1 { self.check_replay(point) } else { Ok(()) };
+            if let Err(e) = guarded.and_then(|()| self.perform_click(point)) {
+                self.log_error(format!("[{}] Attempt {}/{} failed: {}", point.name, attempt, self.max_retries, e));
+                if attempt < self.max_retries && !self.retry_backoff(point, attempt, &e, watchdog) {
+                    return false;
                 }
-            } else {
-                self.log(format!("[DRY RUN] Would click {} at ({}, {})", point.name, point.x, point.y));
+                continue;
             }
             
-            // Success - wait step delay
+            // Success - wait for idle CPU (if enabled), then the point's delay
+            self.count("clicks");
             watchdog.cancel();
-            if !self.sleep_with_check(self.step_delay) {
+            if let Some(threshold) = self.idle_cpu_threshold {
+                if !self.wait_for_idle_cpu(threshold) {
+                    return false;
+                }
+            }
+            let delay = point.delay_override.map_or(self.step_delay, |d| d.max(0));
+            if !self.post_click_delay(delay) {
                 return false;
             }
+            self.capture_step(point);
             watchdog.reset();
             
             return true;
         }
         
-        error!("Failed after {} retries: {}", self.max_retries, point.name);
+        self.log_error(format!("[{}] Failed after {} retries", point.name, self.max_retries));
+        self.capture_click_failure(point);
         false
     }
     
+    /// Wait before the next attempt: `attempt` × 500ms, or 2s when the input
+    /// backend failed to start. Runs with the watchdog cancelled; false if stopped.
+    fn retry_backoff(&self, point: &ClickPoint, attempt: i32, error: &str, watchdog: &WatchdogTimer) -> bool {
+        let backoff = if error.starts_with(ENIGO_FAILURE) {
+            ENIGO_RETRY_BACKOFF
+        } else {
+            RETRY_BACKOFF_STEP * attempt.max(1) as u32
+        };
+        self.log(format!("[{}] Retrying in {}ms", point.name, backoff.as_millis()));
+        
+        watchdog.cancel();
+        let waited = self.wait_while_paused(None)
+            && self.wait_interruptibly(backoff, "backing off before retry").is_ok();
+        watchdog.reset();
+        waited
+    }
+    
+    /// Sleep `seconds` after a click, varied by ±20% in humanize mode
+    fn post_click_delay(&self, seconds: i32) -> bool {
+        let Some(rng) = &self.humanize else {
+            return self.sleep_with_check(seconds);
+        };
+        
+        let factor = 1.0 + rng.borrow_mut().unit() * HUMANIZE_DELAY_FRACTION;
+        let millis = (seconds as f64 * 1000.0 * factor).max(0.0) as u64;
+        self.sleep_with_check((millis / 1000) as i32)
+            && self.wait_interruptibly(Duration::from_millis(millis % 1000), "sleeping").is_ok()
+    }
+    
+    /// `point` moved by a small random offset in humanize mode, never below 0
+    fn humanized(&self, point: &ClickPoint) -> ClickPoint {
+        let mut point = point.clone();
+        if let Some(rng) = &self.humanize {
+            let mut rng = rng.borrow_mut();
+            point.x = (point.x + rng.jitter(HUMANIZE_JITTER_PX)).max(0);
+            point.y = (point.y + rng.jitter(HUMANIZE_JITTER_PX)).max(0);
+        }
+        point
+    }
+    
+    /// Sleep in short slices for `wait`; Err if stopped meanwhile
+    fn wait_interruptibly(&self, wait: Duration, reason: &str) -> Result<(), String> {
+        let deadline = Instant::now() + wait;
+        while Instant::now() < deadline {
+            if !self.is_running() {
+                return Err(format!("Interrupted while {}", reason));
+            }
+            thread::sleep(Duration::from_millis(100).min(deadline - Instant::now()));
+        }
+        Ok(())
+    }
+    
+    /// Sleep (interruptibly) until the rate limiter allows another click
+    fn wait_for_click_slot(&self) -> Result<(), String> {
+        let (Some(interval), Some(last)) = (self.min_click_interval, self.last_click.get()) else {
+            return Ok(());
+        };
+        
+        let elapsed = last.elapsed();
+        if elapsed >= interval {
+            return Ok(());
+        }
+        
+        let wait = interval - elapsed;
+        self.log(format!("Rate limit: waiting {:.1}s before next click", wait.as_secs_f64()));
+        self.wait_interruptibly(wait, "rate limited")
+    }
+    
+    /// Delay a click that repeats the previous click's position too quickly
+    fn check_replay(&self, point: &ClickPoint) -> Result<(), String> {
+        let Some((min_interval, radius)) = self.replay_guard else {
+            return Ok(());
+        };
+        let (Some((x, y)), Some(last)) = (self.last_click_pos.get(), self.last_click.get()) else {
+            return Ok(());
+        };
+        
+        let close = (point.x - x).abs() <= radius && (point.y - y).abs() <= radius;
+        let elapsed = last.elapsed();
+        if !close || elapsed >= min_interval {
+            return Ok(());
+        }
+        
+        let wait = min_interval - elapsed;
+        self.log(format!(
+            "⚠ [{}] Repeat click at ({}, {}) within {:.1}s - delaying {:.1}s",
+            point.name, point.x, point.y, min_interval.as_secs_f64(), wait.as_secs_f64()
+        ));
+        self.wait_interruptibly(wait, "delaying a repeated click")
+    }
+    
+    /// True if `point` currently falls inside the app's own window
+    fn hits_self_window(&self, point: &ClickPoint) -> bool {
+        self.self_window
+            .as_ref()
+            .and_then(|bounds| *bounds.lock().unwrap())
+            .is_some_and(|rect| rect.contains(point.x, point.y))
+    }
+    
+    /// Wait for our own window to move out of the way of `point`
+    fn check_self_click(&self, point: &ClickPoint) -> Result<(), String> {
+        if !self.hits_self_window(point) {
+            return Ok(());
+        }
+        
+        self.log(format!(
+            "⚠ [{}] ({}, {}) is inside the {} window - move it out of the way",
+            point.name, point.x, point.y, crate::APP_TITLE
+        ));
+        
+        let deadline = Instant::now() + Duration::from_secs(SELF_CLICK_WAIT_SEC);
+        while Instant::now() < deadline {
+            if !self.is_running() {
+                return Err("Interrupted while waiting for window to move".to_string());
+            }
+            thread::sleep(Duration::from_millis(500));
+            if !self.hits_self_window(point) {
+                return Ok(());
+            }
+        }
+        
+        Err(format!("[{}] Skipped click on own window", point.name))
+    }
+    
+    /// Move to the point and click once (or log it in dry-run mode)
+    fn perform_click(&self, point: &ClickPoint) -> Result<(), String> {
+        let point = &self.humanized(point);
+        self.check_self_click(point)?;
+        self.wait_for_click_slot()?;
+        self.last_click.set(Some(Instant::now()));
+        self.last_click_pos.set(Some((point.x, point.y)));
+        
+        if self.dry_run {
+            self.log(format!("[DRY RUN] Would {} {} at ({}, {})", point.click_label(), point.name, point.x, point.y));
+            return Ok(());
+        }
+        
+        click_at(point)
+    }
+    
     fn type_text(&self, text: &str) -> Result<(), String> {
         if self.dry_run {
             self.log(format!("[DRY RUN] Would type: {}", text));
@@ -167,31 +798,220 @@ impl AutomationThread {
             Ok(mut enigo) => {
                 enigo.text(text).map_err(|e| format!("Type failed: {}", e))
             }
-            Err(e) => Err(format!("Enigo creation failed: {}", e)),
+            Err(e) => Err(format!("{}: {}", ENIGO_FAILURE, e)),
         }
     }
     
-    pub fn run(mut self) {
-        info!("Automation thread started");
-        self.update_status("Status: Running");
+    /// Press a platform shortcut (Ctrl+key, or Cmd+key on macOS)
+    fn shortcut(enigo: &mut Enigo, key: char) -> Result<(), String> {
+        let modifier = if cfg!(target_os = "macos") { Key::Meta } else { Key::Control };
         
-        // Setup watchdog
+        enigo.key(modifier, Direction::Press)
+            .map_err(|e| format!("Key press failed: {}", e))?;
+        let result = enigo.key(Key::Unicode(key), Direction::Click)
+            .map_err(|e| format!("Key press failed: {}", e));
+        // Always release the modifier, even if the key itself failed
+        let _ = enigo.key(modifier, Direction::Release);
+        result
+    }
+    
+    /// Select the focused field's contents and read them back via the clipboard,
+    /// restoring the previous clipboard text afterwards
+    fn read_back_field(&self) -> Result<String, String> {
+        let mut clipboard = arboard::Clipboard::new()
+            .map_err(|e| format!("Clipboard unavailable: {}", e))?;
+        let previous = clipboard.get_text().ok();
+        
+        let mut enigo = Enigo::new(&Settings::default())
+            .map_err(|e| format!("{}: {}", ENIGO_FAILURE, e))?;
+        Self::shortcut(&mut enigo, 'a')?;
+        Self::shortcut(&mut enigo, 'c')?;
+        thread::sleep(Duration::from_millis(150));
+        
+        let text = clipboard.get_text()
+            .map_err(|e| format!("Clipboard read failed: {}", e));
+        
+        if let Some(previous) = previous {
+            let _ = clipboard.set_text(previous);
+        }
+        
+        text
+    }
+    
+    /// Type `text` into the field at `point`, verifying it via clipboard read-back if enabled
+    fn enter_text(&self, point: &ClickPoint, text: &str) -> Result<(), String> {
+        self.type_text(text)?;
+        
+        if !self.verify_text || self.dry_run {
+            return Ok(());
+        }
+        
+        for attempt in 1..=self.max_retries {
+            thread::sleep(Duration::from_millis(200));
+            
+            match self.read_back_field() {
+                Ok(actual) if actual.trim() == text.trim() => {
+                    self.log(format!("[{}] Verified text: {}", point.name, text));
+                    return Ok(());
+                }
+                Ok(actual) => {
+                    self.log(format!(
+                        "[{}] Text mismatch (attempt {}/{}): expected '{}', found '{}'",
+                        point.name, attempt, self.max_retries, text, actual
+                    ));
+                }
+                Err(e) => {
+                    self.log(format!("[{}] Text verification failed: {}", point.name, e));
+                }
+            }
+            
+            if attempt == self.max_retries || !self.is_running() {
+                break;
+            }
+            
+            // Refocus the field, select its contents and type over them
+            self.perform_click(point)?;
+            thread::sleep(Duration::from_millis(200));
+            let mut enigo = Enigo::new(&Settings::default())
+                .map_err(|e| format!("{}: {}", ENIGO_FAILURE, e))?;
+            Self::shortcut(&mut enigo, 'a')?;
+            self.type_text(text)?;
+        }
+        
+        Err(format!("[{}] Typed text could not be verified", point.name))
+    }
+    
+    fn create_watchdog(&self) -> WatchdogTimer {
         let tx_clone = self.tx_to_gui.clone();
         let stop_clone = self.stop_flag.clone();
-        let watchdog = WatchdogTimer::new(
-            (self.max_retries as u64 * 3).max(30),
+        WatchdogTimer::new(
+            watchdog_timeout_sec(self.max_retries),
             move || {
                 error!("Watchdog timeout - automation unresponsive");
                 let _ = tx_clone.send(AutomationMessage::Log("⚠ Watchdog timeout".to_string()));
                 let _ = tx_clone.send(AutomationMessage::Status("Status: Error - Timeout".to_string()));
                 stop_clone.store(true, Ordering::SeqCst);
             },
-        );
+        )
+    }
+    
+    /// Execute the sequence a single time on the calling thread (no looping)
+    pub fn run_once(mut self) -> Result<(), String> {
+        info!("Single sequence run started");
+        self.single_pass = true;
+        
+        let watchdog = self.create_watchdog();
+        let result = self.automation_loop(&watchdog);
         
-        // Run automation
-        if let Err(e) = self.automation_loop(&watchdog) {
+        self.stop_flag.store(true, Ordering::SeqCst);
+        info!("Single sequence run finished");
+        result
+    }
+    
+    /// Storyboard screenshot after a successful click
+    fn capture_step(&self, point: &ClickPoint) {
+        if !self.capture_each_step {
+            return;
+        }
+        let Some(screenshots) = self.screenshots.as_ref() else {
+            return;
+        };
+
+        if self.wait_interruptibly(self.screenshot_settle, "waiting to take step screenshot").is_err() {
+            return;
+        }
+        
+        if let Some(path) = screenshots.capture(&point.name, "after") {
+            self.log(format!("[{}] Screenshot: {}", point.name, path));
+        }
+    }
+    
+    /// Screenshot of the screen right after a click ran out of retries. It
+    /// replaces the generic failure screenshot for this run.
+    fn capture_click_failure(&self, point: &ClickPoint) {
+        let Some(screenshots) = self.screenshots.as_ref().filter(|s| s.is_enabled()) else {
+            return;
+        };
+        
+        if self.wait_interruptibly(self.screenshot_settle, "waiting to take failure screenshot").is_err() {
+            return;
+        }
+        
+        if let Some(path) = screenshots.capture(&point.name, "error") {
+            self.log(format!("[{}] Failure screenshot: {}", point.name, path));
+            self.failure_captured.set(true);
+        }
+    }
+    
+    /// Save a screenshot (and optional screen clip) of the failure state
+    fn capture_failure(&self) {
+        let Some(screenshots) = self.screenshots.as_ref().filter(|s| s.is_enabled()) else {
+            return;
+        };
+        
+        if !self.failure_captured.get() {
+            // The run is already stopping, so this pause can't be interrupted
+            thread::sleep(self.screenshot_settle);
+            if let Some(path) = screenshots.capture("failure", "error") {
+                self.log(format!("Failure screenshot: {}", path));
+            }
+        }
+        
+        if self.error_clip_sec > 0 {
+            self.log(format!("Recording {}s failure clip...", self.error_clip_sec));
+            match screenshots.record_clip("failure", "error", self.error_clip_sec, &self.stop_flag) {
+                Some(path) => self.log(format!("Failure clip: {}", path)),
+                None => self.log("Failure clip not recorded (requires screenshots + video features)"),
+            }
+        }
+    }
+    
+    /// Show "Starting in N..." in the status; false if stopped meanwhile
+    fn start_countdown(&self) -> bool {
+        for remaining in (1..=self.start_delay_sec).rev() {
+            if !self.is_running() {
+                break;
+            }
+            self.update_status(format!("Status: Starting in {}...", remaining));
+            thread::sleep(Duration::from_secs(1));
+        }
+        // Also catches a stop during the last second
+        if !self.is_running() {
+            if self.start_delay_sec > 0 {
+                self.log("Interrupted during start countdown");
+            }
+            return false;
+        }
+        true
+    }
+    
+    pub fn run(mut self) {
+        info!("Automation thread started");
+        
+        if self.start_delay_sec > 0 {
+            self.log(format!("Starting in {} seconds", self.start_delay_sec));
+        }
+        let started = self.start_countdown();
+        if started {
+            self.update_status("Status: Running");
+        }
+        
+        // Setup watchdog
+        let watchdog = self.create_watchdog();
+        
+        // Run automation (or a single calibration pass)
+        let result = if !started {
+            Ok(())
+        } else if self.calibrate {
+            self.calibration_run(&watchdog)
+        } else {
+            self.automation_loop(&watchdog)
+        };
+        
+        if let Err(e) = result {
             error!("Automation error: {}", e);
             self.error_popup(format!("Automation Error: {}", e));
+            self.capture_failure();
         }
         
         // Cleanup
@@ -200,98 +1020,412 @@ impl AutomationThread {
         info!("Automation thread stopped");
     }
     
+    /// Expand the configured points into this run's steps.
+    ///
+    /// If no point has an action, the first six points follow the classic
+    /// choreography (text entry on point 2, Step 4 wait, long wait after
+    /// point 4). Otherwise each point runs its own action (default: click).
+    fn resolve_steps(&self) -> Result<Vec<(ClickPoint, StepAction)>, String> {
+        if self.points.is_empty() {
+            return Err("No steps configured".to_string());
+        }
+        validate_date_format(&self.date_format)?;
+        
+        if self.points.iter().any(|p| p.action.is_some()) {
+            return Ok(self.points
+                .iter()
+                .map(|p| (p.clone(), p.action.clone().unwrap_or(StepAction::Click)))
+                .collect());
+        }
+        
+        if self.points.len() < CLASSIC_STEP_COUNT {
+            return Err(format!(
+                "The classic sequence needs {} points but {} are configured - add points or give each one an action",
+                CLASSIC_STEP_COUNT,
+                self.points.len()
+            ));
+        }
+        if self.points.len() > CLASSIC_STEP_COUNT {
+            self.log(format!(
+                "Ignoring {} extra point(s): the classic sequence uses the first {}",
+                self.points.len() - CLASSIC_STEP_COUNT,
+                CLASSIC_STEP_COUNT
+            ));
+        }
+        
+        let p = &self.points;
+        let marker = |name: &str| ClickPoint::new(name, 0, 0);
+        Ok(vec![
+            (p[0].clone(), StepAction::Click),
+            (p[1].clone(), StepAction::TypeText(self.text_template.clone())),
+            (p[2].clone(), StepAction::Click),
+            (marker("Step 4"), StepAction::Wait(self.step4_wait_sec as u32)),
+            (p[3].clone(), StepAction::Click),
+            (marker("Step 6"), StepAction::LongWait),
+            (p[4].clone(), StepAction::Click),
+            (p[5].clone(), StepAction::Click),
+        ])
+    }
+    
+    /// Run the long wait with timer updates and progress milestones; false if stopped
+    fn long_wait(&self, name: &str) -> bool {
+        let hours = self.total_seconds / 3600;
+        let minutes = (self.total_seconds % 3600) / 60;
+        self.log(format!("{}: Long wait {}h {}m", name, hours, minutes));
+        
+        let mut remaining = self.total_seconds;
+        while remaining > 0 && self.is_running() {
+            if !self.wait_while_paused(None) {
+                break;
+            }
+            self.update_timer(remaining);
+            thread::sleep(Duration::from_secs(1));
+            remaining -= 1;
+            
+            let elapsed = self.total_seconds - remaining;
+            if self.wait_milestone_sec > 0 && remaining > 0 && elapsed % self.wait_milestone_sec == 0 {
+                self.log(format!(
+                    "Long wait: {}h {}m remaining ({}% done)",
+                    remaining / 3600,
+                    (remaining % 3600) / 60,
+                    elapsed * 100 / self.total_seconds
+                ));
+            }
+        }
+        
+        if !self.is_running() {
+            return false;
+        }
+        
+        self.log("Long wait completed");
+        true
+    }
+    
+    /// Click the focus point (if configured); Ok(false) if stopped
+    fn focus_target(&self) -> Result<bool, String> {
+        let Some(focus) = &self.focus_point else {
+            return Ok(true);
+        };
+        
+        self.log(format!("[{}] Focus click at ({}, {})", focus.name, focus.x, focus.y));
+        if let Err(e) = self.perform_click(focus) {
+            if !self.is_running() {
+                return Ok(false);
+            }
+            return Err(format!("Focus click failed: {}", e));
+        }
+        
+        // Give the window a moment to take focus
+        Ok(self.wait_interruptibly(FOCUS_SETTLE, "waiting for focus").is_ok())
+    }
+    
+    /// Execute one step; Ok(false) if automation was stopped during it
+    fn run_step(
+        &self,
+        point: &ClickPoint,
+        action: &StepAction,
+        iteration: u32,
+        watchdog: &WatchdogTimer,
+    ) -> Result<bool, String> {
+        if action.clicks() {
+            watchdog.reset();
+            if !self.execute_click(point, watchdog) {
+                return if self.is_running() {
+                    Err(format!("Failed: {}", point.name))
+                } else {
+                    Ok(false)
+                };
+            }
+        }
+        
+        match action {
+            StepAction::Click => Ok(true),
+            StepAction::TypeText(_) | StepAction::TypeDate(_) => {
+                let text = match action {
+                    StepAction::TypeDate(format) => format_date(Local::now(), format),
+                    StepAction::TypeText(template) => {
+                        render_template_with(template, Local::now(), iteration, &self.date_format)
+                    }
+                    _ => unreachable!(),
+                };
+                self.enter_text(point, &text)?;
+                self.log(format!("Entered text: {}", text));
+                watchdog.cancel();
+                let completed = self.sleep_with_check(2);
+                watchdog.reset();
+                Ok(completed)
+            }
+            StepAction::Wait(seconds) => {
+                self.log(format!("{}: Waiting {} seconds", point.name, seconds));
+                watchdog.cancel();
+                let completed = self.sleep_with_check(*seconds as i32);
+                watchdog.reset();
+                Ok(completed)
+            }
+            StepAction::LongWait => {
+                watchdog.cancel();
+                if !self.long_wait(&point.name) {
+                    return Ok(false);
+                }
+                let completed = self.sleep_with_check(2);
+                watchdog.reset();
+                Ok(completed)
+            }
+        }
+    }
+    
     fn automation_loop(&mut self, watchdog: &WatchdogTimer) -> Result<(), String> {
+        let steps = self.resolve_steps()?;
         let mut iteration = 0;
         
-        while self.is_running() {
+        'run: while self.is_running() {
             iteration += 1;
             self.log(format!("===== Iteration {} =====", iteration));
             
-            // Step 1
-            watchdog.reset();
-            if !self.execute_click(&self.points[0], watchdog) {
-                return Err(format!("Failed: {}", self.points[0].name));
+            if !self.focus_target()? {
+                break;
             }
             
-            // Step 2 - Click date field and enter date in DD-MM-YYYY format
-            watchdog.reset();
-            if !self.execute_click(&self.points[1], watchdog) {
-                return Err(format!("Failed: {}", self.points[1].name));
+            for (point, action) in &steps {
+                if !self.wait_while_paused(Some(watchdog)) {
+                    break 'run;
+                }
+                if !self.run_step(point, action, iteration, watchdog)? {
+                    break 'run;
+                }
             }
             
-            let date = Local::now().format("%d-%m-%Y").to_string();
-            self.type_text(&date)?;
-            self.log(format!("Entered date: {}", date));
+            self.log(format!("===== Iteration {} complete =====", iteration));
+            self.count("iterations");
             
-            if !self.sleep_with_check(2) {
+            if self.single_pass {
                 break;
             }
             
-            // Step 3
-            watchdog.reset();
-            if !self.execute_click(&self.points[2], watchdog) {
-                return Err(format!("Failed: {}", self.points[2].name));
+            if let Some(max) = self.max_iterations.filter(|&max| iteration >= max) {
+                self.log(format!("Completed {}/{} iterations", iteration, max));
+                self.update_status(format!("Status: Completed {}/{} iterations", iteration, max));
+                break;
             }
             
-            // Step 4 - Short wait
-            self.log(format!("Step 4: Waiting {} seconds", self.step4_wait_sec));
             watchdog.cancel();
-            if !self.sleep_with_check(self.step4_wait_sec) {
+            if !self.sleep_with_check(5) {
                 break;
             }
             watchdog.reset();
-            
-            // Step 5
-            watchdog.reset();
-            if !self.execute_click(&self.points[3], watchdog) {
-                return Err(format!("Failed: {}", self.points[3].name));
-            }
-            
-            // Step 6 - Long wait
-            let hours = self.total_seconds / 3600;
-            let minutes = (self.total_seconds % 3600) / 60;
-            self.log(format!("Step 6: Long wait {}h {}m", hours, minutes));
-            watchdog.cancel();
-            
-            let mut remaining = self.total_seconds;
-            while remaining > 0 && self.is_running() {
-                self.update_timer(remaining);
-                thread::sleep(Duration::from_secs(1));
-                remaining -= 1;
+        }
+        
+        Ok(())
+    }
+    
+    // ========================================================================
+    // CALIBRATION
+    // ========================================================================
+    
+    /// Execute each step once, timing how long the screen takes to react
+    fn calibration_run(&self, watchdog: &WatchdogTimer) -> Result<(), String> {
+        if self.dry_run {
+            return Err("Calibration needs real clicks - disable Dry Run".to_string());
+        }
+        
+        let screenshots = self.screenshots.as_ref()
+            .ok_or("Calibration requires a screenshot manager")?;
+        if screenshots.sample().is_none() {
+            return Err("Calibration requires the screenshots feature and a working capture backend".to_string());
+        }
+        
+        let plan = self.resolve_steps()?;
+        
+        self.log("===== Calibration run =====");
+        watchdog.cancel();
+        
+        let mut steps = Vec::with_capacity(plan.len());
+        // Timing of the click that precedes the first fixed wait (Step 3 classically)
+        let mut before_wait: Option<i32> = None;
+        
+        for (point, action) in &plan {
+            if !self.is_running() {
+                return Err("Calibration interrupted".to_string());
             }
             
-            if !self.is_running() {
-                break;
+            // Waits are what calibration is measuring for, so don't run them
+            if !action.clicks() {
+                if matches!(action, StepAction::Wait(_)) && before_wait.is_none() {
+                    before_wait = Some(
+                        steps.last()
+                            .filter(|s: &&StepTiming| !s.timed_out)
+                            .map_or(self.step4_wait_sec, |s| s.suggested_delay_sec),
+                    );
+                }
+                continue;
             }
             
-            self.log("Long wait completed");
-            watchdog.reset();
+            let baseline = screenshots.sample();
+            self.perform_click(point)?;
             
-            if !self.sleep_with_check(2) {
-                break;
+            // Typed text is part of what the UI reacts to
+            match action {
+                StepAction::TypeText(template) => {
+                    self.type_text(&render_template_with(template, Local::now(), 1, &self.date_format))?
+                }
+                StepAction::TypeDate(format) => self.type_text(&format_date(Local::now(), format))?,
+                _ => {}
             }
             
-            // Step 7
-            watchdog.reset();
-            if !self.execute_click(&self.points[4], watchdog) {
-                return Err(format!("Failed: {}", self.points[4].name));
-            }
+            let measured = self.wait_for_screen_change(screenshots, baseline.as_ref());
+            let suggested = match measured {
+                Some(elapsed) => (elapsed.as_secs_f64() * 1.5).ceil() as i32 + 1,
+                None => self.step_delay,
+            };
             
-            // Step 8
-            watchdog.reset();
-            if !self.execute_click(&self.points[5], watchdog) {
-                return Err(format!("Failed: {}", self.points[5].name));
+            match measured {
+                Some(elapsed) => self.log(format!(
+                    "[{}] Screen changed after {:.2}s (suggest {}s)",
+                    point.name, elapsed.as_secs_f64(), suggested
+                )),
+                None => self.log(format!(
+                    "[{}] No screen change within {}s",
+                    point.name, CALIBRATION_TIMEOUT_SEC
+                )),
             }
             
-            self.log(format!("===== Iteration {} complete =====", iteration));
-            
-            watchdog.cancel();
-            if !self.sleep_with_check(5) {
-                break;
+            steps.push(StepTiming {
+                name: point.name.clone(),
+                measured_ms: measured.map(|d| d.as_millis() as u64),
+                suggested_delay_sec: suggested,
+                timed_out: measured.is_none(),
+            });
+        }
+        
+        let timed_out: Vec<&str> = steps.iter().filter(|s| s.timed_out).map(|s| s.name.as_str()).collect();
+        if !timed_out.is_empty() {
+            self.log(format!(
+                "No screen change for {}; not used for the suggested step delay",
+                timed_out.join(", ")
+            ));
+        }
+        
+        let report = CalibrationReport {
+            suggested_step_delay: steps
+                .iter()
+                .filter(|s| !s.timed_out)
+                .map(|s| s.suggested_delay_sec)
+                .max()
+                .unwrap_or(self.step_delay),
+            // Step 4 waits on whatever the preceding click triggered
+            suggested_step4_wait: before_wait.unwrap_or(self.step4_wait_sec),
+            steps,
+        };
+        
+        self.log(format!(
+            "Calibration complete: step delay {}s, step 4 wait {}s",
+            report.suggested_step_delay, report.suggested_step4_wait
+        ));
+        
+        let report_path = paths::data_file(CALIBRATION_REPORT_FILE);
+        match std::fs::File::create(&report_path) {
+            Ok(file) => {
+                if serde_json::to_writer_pretty(file, &report).is_ok() {
+                    self.log(format!("Calibration report saved to {}", report_path.display()));
+                }
             }
-            watchdog.reset();
+            Err(e) => error!("Failed to write {}: {}", report_path.display(), e),
         }
         
+        let _ = self.tx_to_gui.send(AutomationMessage::Calibration(report));
         Ok(())
     }
+    
+    /// Poll the screen until it differs from `baseline`; None on timeout or stop
+    fn wait_for_screen_change(
+        &self,
+        screenshots: &ScreenshotManager,
+        baseline: Option<&ScreenSample>,
+    ) -> Option<Duration> {
+        let baseline = baseline?;
+        let start = Instant::now();
+        
+        while start.elapsed() < Duration::from_secs(CALIBRATION_TIMEOUT_SEC) && self.is_running() {
+            thread::sleep(Duration::from_millis(CALIBRATION_POLL_MS));
+            
+            if let Some(current) = screenshots.sample() {
+                if current.difference(baseline) > CALIBRATION_CHANGE_THRESHOLD {
+                    return Some(start.elapsed());
+                }
+            }
+        }
+        
+        None
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(year: i32, month: u32, day: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(year, month, day, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn format_date_leap_day() {
+        assert_eq!(format_date(at(2024, 2, 29), "%d-%m-%Y"), "29-02-2024");
+        assert_eq!(format_date(at(2024, 2, 29), "%Y-%m-%d"), "2024-02-29");
+    }
+
+    #[test]
+    fn format_date_single_digit_day() {
+        assert_eq!(format_date(at(2024, 3, 5), "%d"), "05");
+        assert_eq!(format_date(at(2024, 3, 5), "%-d"), "5");
+    }
+
+    #[test]
+    fn format_date_invalid_specifier_is_empty() {
+        assert_eq!(format_date(at(2024, 3, 5), "%Q"), "");
+    }
+
+    #[test]
+    fn validate_date_format_rejects_invalid_and_blank() {
+        assert!(validate_date_format(DEFAULT_DATE_FORMAT).is_ok());
+        assert!(validate_date_format("%Y-%m-%d %H:%M").is_ok());
+        assert!(validate_date_format("%Q").is_err());
+        assert!(validate_date_format("").is_err());
+        assert!(validate_date_format("   ").is_err());
+    }
+
+    #[test]
+    fn render_template_expands_placeholders() {
+        let now = at(2024, 3, 5);
+        assert_eq!(render_template("{date} #{iteration}", now, 7), "05-03-2024 #7");
+        assert_eq!(render_template_with("{date}", now, 1, "%Y/%m/%d"), "2024/03/05");
+        assert_eq!(render_template("{date:%Y} {time:%H}", now, 1), "2024 12");
+    }
+
+    #[test]
+    fn render_template_escapes_braces() {
+        assert_eq!(render_template("{{iteration}} }}", at(2024, 3, 5), 7), "{iteration} }");
+    }
+
+    #[test]
+    fn render_template_keeps_unknown_and_unterminated() {
+        let now = at(2024, 3, 5);
+        assert_eq!(render_template("{foo} {iteration:x} {env}", now, 1), "{foo} {iteration:x} {env}");
+        assert_eq!(render_template("a {date", now, 1), "a {date");
+        assert_eq!(render_template("a } b", now, 1), "a } b");
+    }
+
+    #[test]
+    fn render_template_missing_env_is_empty() {
+        assert_eq!(render_template("[{env:SECCAMCLOUD_TEST_UNSET_VAR}]", at(2024, 3, 5), 1), "[]");
+    }
+
+    #[test]
+    fn render_template_keeps_invalid_format() {
+        assert_eq!(render_template("{date:%Q} {time:%Q}", at(2024, 3, 5), 1), "{date:%Q} {time:%Q}");
+    }
 }
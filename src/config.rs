@@ -6,23 +6,96 @@
 // License: GPLv2
 // ============================================================================
 
-use std::fs::File;
-use std::io::BufReader;
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::path::Path;
-use log::{info, warn};
+use std::rc::Rc;
+use std::time::SystemTime;
+use log::{error, info, warn};
 use serde::{Serialize, Deserialize};
 use lazy_static::lazy_static;
 
+use crate::paths;
+
 // ============================================================================
 // DATA STRUCTURES
 // ============================================================================
 
+/// What an automation step does when it is reached
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StepAction {
+    /// Click the point
+    Click,
+    /// Click the point, then type the text (supports template placeholders)
+    TypeText(String),
+    /// Click the point, then type the current date in this strftime format
+    TypeDate(String),
+    /// Pause for this many seconds (coordinates are ignored)
+    Wait(u32),
+    /// Run the configured long wait with countdown (coordinates are ignored)
+    LongWait,
+}
+
+impl StepAction {
+    /// True if the step clicks its point
+    pub fn clicks(&self) -> bool {
+        matches!(self, StepAction::Click | StepAction::TypeText(_) | StepAction::TypeDate(_))
+    }
+
+    /// Short description for logs and the GUI
+    pub fn label(&self) -> String {
+        match self {
+            StepAction::Click => "click".to_string(),
+            StepAction::TypeText(text) => format!("click + type \"{}\"", text),
+            StepAction::TypeDate(format) => format!("click + type date {}", format),
+            StepAction::Wait(secs) => format!("wait {}s", secs),
+            StepAction::LongWait => "long wait".to_string(),
+        }
+    }
+}
+
+/// Mouse button pressed at a click point
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ClickButton {
+    #[default]
+    Left,
+    Right,
+    Middle,
+}
+
+/// Whether a click point is clicked once or twice
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ClickKind {
+    #[default]
+    Single,
+    Double,
+}
+
+fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
 /// Click point with coordinates and descriptive name
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ClickPoint {
     pub name: String,
     pub x: i32,
     pub y: i32,
+    /// Step behavior. When no point in a sequence has an action, the points
+    /// follow the classic six-step choreography (see [`DEFAULT_POINTS`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<StepAction>,
+    /// Omitted from the JSON when left/single, so older files load unchanged
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub button: ClickButton,
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub click_kind: ClickKind,
+    /// Seconds to wait after this point's click instead of the global step delay
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delay_override: Option<i32>,
 }
 
 impl ClickPoint {
@@ -31,11 +104,49 @@ impl ClickPoint {
             name: name.into(),
             x,
             y,
+            action: None,
+            button: ClickButton::default(),
+            click_kind: ClickKind::default(),
+            delay_override: None,
+        }
+    }
+
+    pub fn with_action(mut self, action: StepAction) -> Self {
+        self.action = Some(action);
+        self
+    }
+
+    pub fn with_click(mut self, button: ClickButton, kind: ClickKind) -> Self {
+        self.button = button;
+        self.click_kind = kind;
+        self
+    }
+
+    pub fn with_delay(mut self, seconds: i32) -> Self {
+        self.delay_override = Some(seconds.max(0));
+        self
+    }
+
+    /// "click", "double-click", "right-click", "double right-click"...
+    pub fn click_label(&self) -> String {
+        let button = match self.button {
+            ClickButton::Left => "",
+            ClickButton::Right => "right-",
+            ClickButton::Middle => "middle-",
+        };
+        match self.click_kind {
+            ClickKind::Single => format!("{}click", button),
+            ClickKind::Double if button.is_empty() => "double-click".to_string(),
+            ClickKind::Double => format!("double {}click", button),
         }
     }
 }
 
-/// Default automation click points
+/// Default automation click points.
+///
+/// These carry no actions, so they run the classic choreography: click 1,
+/// click 2 and type the text template, click 3, Step 4 wait, click 4 (Step 5),
+/// long wait (Step 6), then click 5 and 6 (Steps 7 and 8).
 lazy_static! {
     pub static ref DEFAULT_POINTS: Vec<ClickPoint> = vec![
         ClickPoint::new("Step 1", 3514, 1640),
@@ -61,26 +172,372 @@ impl Default for AppConfig {
     }
 }
 
+/// Output options for [`save_points_with`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SaveOptions {
+    /// Pretty-print (indented) instead of compact single-line JSON
+    pub pretty: bool,
+    /// Write each point's fields in alphabetical order for stable diffs. The
+    /// points themselves keep their order, which is the click order.
+    pub sort_keys: bool,
+}
+
+impl Default for SaveOptions {
+    fn default() -> Self {
+        Self {
+            pretty: true,
+            sort_keys: false,
+        }
+    }
+}
+
+impl SaveOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_pretty(mut self, pretty: bool) -> Self {
+        self.pretty = pretty;
+        self
+    }
+
+    pub fn with_sort_keys(mut self, sort: bool) -> Self {
+        self.sort_keys = sort;
+        self
+    }
+}
+
+/// Step executed when the emergency-stop hotkey fires
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum EmergencyAction {
+    /// Stop the automation thread
+    StopAutomation,
+    /// Capture a screenshot of the current screen state
+    Screenshot,
+    /// Record the emergency and the session summary in telemetry
+    FlushTelemetry,
+    /// Click a cleanup sequence (e.g. close the automated app)
+    Click { points: Vec<ClickPoint> },
+}
+
+/// Hotkey configuration (hotkeys.json)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HotkeyConfig {
+    /// Emergency stop key, e.g. "Delete", "F12" or "ctrl+shift+KeyQ"
+    #[serde(default = "default_emergency_key")]
+    pub emergency_key: String,
+    /// Pauses or resumes a running sequence ("" = no key)
+    #[serde(default = "default_pause_key")]
+    pub pause_key: String,
+    /// Takes a screenshot, running or not ("" = no key)
+    #[serde(default = "default_screenshot_key")]
+    pub screenshot_key: String,
+    /// Actions run in order when the emergency hotkey is pressed
+    #[serde(default = "default_emergency_actions")]
+    pub emergency_actions: Vec<EmergencyAction>,
+}
+
+pub const DEFAULT_EMERGENCY_KEY: &str = "Delete";
+pub const DEFAULT_PAUSE_KEY: &str = "F9";
+pub const DEFAULT_SCREENSHOT_KEY: &str = "F10";
+
+fn default_emergency_key() -> String {
+    DEFAULT_EMERGENCY_KEY.to_string()
+}
+
+fn default_date_format() -> String {
+    crate::automation::DEFAULT_DATE_FORMAT.to_string()
+}
+
+fn default_pause_key() -> String {
+    DEFAULT_PAUSE_KEY.to_string()
+}
+
+fn default_screenshot_key() -> String {
+    DEFAULT_SCREENSHOT_KEY.to_string()
+}
+
+fn default_emergency_actions() -> Vec<EmergencyAction> {
+    vec![EmergencyAction::StopAutomation]
+}
+
+impl Default for HotkeyConfig {
+    fn default() -> Self {
+        Self {
+            emergency_key: default_emergency_key(),
+            pause_key: default_pause_key(),
+            screenshot_key: default_screenshot_key(),
+            emergency_actions: default_emergency_actions(),
+        }
+    }
+}
+
+/// Unsaved GUI edits, written periodically to [`AUTOSAVE_FILE`] while editing
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Autosave {
+    /// RFC 3339 local time of the snapshot
+    pub saved_at: String,
+    pub points: Vec<ClickPoint>,
+    pub total_hours: i32,
+    pub total_minutes: i32,
+    pub step_delay: i32,
+    pub max_retries: i32,
+    pub step4_wait: i32,
+    /// Missing in autosaves from older versions
+    #[serde(default = "default_date_format")]
+    pub date_format: String,
+}
+
+pub const AUTOSAVE_FILE: &str = "clickpoints.autosave.json";
+
+// ============================================================================
+// STORAGE BACKENDS
+// ============================================================================
+
+/// Where configuration files are read from and written to.
+///
+/// The filesystem backend is used unless a thread has selected another one
+/// with [`with_config_backend`] (e.g. [`MemoryBackend`] in tests).
+pub trait ConfigBackend {
+    /// Contents of `name`, None if it doesn't exist
+    fn read(&self, name: &str) -> Option<Result<String, String>>;
+    fn write(&self, name: &str, contents: &str) -> Result<(), String>;
+    /// Delete `name`; a missing file is not an error
+    fn remove(&self, name: &str) -> Result<(), String>;
+    /// Last modification time of `name`, if the backend tracks one
+    fn modified(&self, _name: &str) -> Option<SystemTime> {
+        None
+    }
+    /// Load the backup configuration (confy on disk)
+    fn load_backup(&self) -> Result<AppConfig, String>;
+    fn store_backup(&self, cfg: &AppConfig) -> Result<(), String>;
+    /// Human-readable location of `name` for messages
+    fn location(&self, name: &str) -> String;
+}
+
+/// Files in the data directory, with the confy backup alongside them
+pub struct FileBackend;
+
+impl ConfigBackend for FileBackend {
+    fn read(&self, name: &str) -> Option<Result<String, String>> {
+        let path = paths::data_file(name);
+        path.exists().then(|| std::fs::read_to_string(&path).map_err(|e| e.to_string()))
+    }
+
+    fn write(&self, name: &str, contents: &str) -> Result<(), String> {
+        std::fs::write(paths::data_file(name), contents).map_err(|e| e.to_string())
+    }
+
+    fn remove(&self, name: &str) -> Result<(), String> {
+        match std::fs::remove_file(paths::data_file(name)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.to_string()),
+            _ => Ok(()),
+        }
+    }
+
+    fn modified(&self, name: &str) -> Option<SystemTime> {
+        std::fs::metadata(paths::data_file(name)).and_then(|m| m.modified()).ok()
+    }
+
+    fn load_backup(&self) -> Result<AppConfig, String> {
+        load_confy().map_err(|e| e.to_string())
+    }
+
+    fn store_backup(&self, cfg: &AppConfig) -> Result<(), String> {
+        confy::store_path(paths::confy_path(), cfg).map_err(|e| e.to_string())
+    }
+
+    fn location(&self, name: &str) -> String {
+        paths::data_file(name).display().to_string()
+    }
+}
+
+/// In-memory files and backup; nothing touches the disk
+#[derive(Default)]
+pub struct MemoryBackend {
+    files: RefCell<HashMap<String, String>>,
+    backup: RefCell<Option<AppConfig>>,
+}
+
+impl MemoryBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pre-populate a file
+    pub fn with_file(self, name: &str, contents: impl Into<String>) -> Self {
+        self.files.borrow_mut().insert(name.to_string(), contents.into());
+        self
+    }
+
+    /// Current contents of a file, e.g. to inspect what was saved
+    pub fn file(&self, name: &str) -> Option<String> {
+        self.files.borrow().get(name).cloned()
+    }
+}
+
+impl ConfigBackend for MemoryBackend {
+    fn read(&self, name: &str) -> Option<Result<String, String>> {
+        self.files.borrow().get(name).cloned().map(Ok)
+    }
+
+    fn write(&self, name: &str, contents: &str) -> Result<(), String> {
+        self.files.borrow_mut().insert(name.to_string(), contents.to_string());
+        Ok(())
+    }
+
+    fn remove(&self, name: &str) -> Result<(), String> {
+        self.files.borrow_mut().remove(name);
+        Ok(())
+    }
+
+    fn load_backup(&self) -> Result<AppConfig, String> {
+        self.backup.borrow().clone().ok_or_else(|| "no backup stored".to_string())
+    }
+
+    fn store_backup(&self, cfg: &AppConfig) -> Result<(), String> {
+        *self.backup.borrow_mut() = Some(cfg.clone());
+        Ok(())
+    }
+
+    fn location(&self, name: &str) -> String {
+        format!("memory:{}", name)
+    }
+}
+
+thread_local! {
+    static BACKEND: RefCell<Option<Rc<dyn ConfigBackend>>> = const { RefCell::new(None) };
+}
+
+/// Run `f` with this thread's config functions using `backend`; the previous
+/// backend is restored afterwards, even if `f` panics
+pub fn with_config_backend<R>(backend: Rc<dyn ConfigBackend>, f: impl FnOnce() -> R) -> R {
+    let _restore = RestoreBackend(BACKEND.with(|b| b.replace(Some(backend))));
+    f()
+}
+
+/// Puts the previous backend back when dropped
+struct RestoreBackend(Option<Rc<dyn ConfigBackend>>);
+
+impl Drop for RestoreBackend {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        BACKEND.with(|b| *b.borrow_mut() = previous);
+    }
+}
+
+/// The backend selected for this thread, or the filesystem
+fn backend() -> Rc<dyn ConfigBackend> {
+    BACKEND.with(|b| b.borrow().clone()).unwrap_or_else(|| Rc::new(FileBackend))
+}
+
 // ============================================================================
 // CONFIGURATION MANAGEMENT
 // ============================================================================
 
-/// Load click points from configuration
+/// Load hotkey configuration from hotkeys.json, or defaults if absent
+pub fn load_hotkey_config() -> HotkeyConfig {
+    if let Some(contents) = backend().read("hotkeys.json") {
+        if let Ok(cfg) = contents.and_then(|c| serde_json::from_str::<HotkeyConfig>(&c).map_err(|e| e.to_string())) {
+            info!("Loaded {} emergency actions from hotkeys.json", cfg.emergency_actions.len());
+            return cfg;
+        }
+        warn!("Failed to parse hotkeys.json, using default hotkey config");
+    }
+    
+    HotkeyConfig::default()
+}
+
+/// Load click points from configuration.
+///
+/// An unreadable or invalid clickpoints.json is logged as an error and the
+/// confy/default points are used; see [`try_load_points`] to detect that case.
 pub fn load_points() -> Vec<ClickPoint> {
+    try_load_points().unwrap_or_else(|_| load_backup_points())
+}
+
+/// Like [`load_points`], but returns an error (instead of falling back) when
+/// clickpoints.json exists and can't be read or parsed
+pub fn try_load_points() -> Result<Vec<ClickPoint>, String> {
     // Try JSON file first (preferred format)
-    let json_path = Path::new("clickpoints.json");
-    if json_path.exists() {
-        if let Ok(file) = File::open(json_path) {
-            if let Ok(points) = serde_json::from_reader::<_, Vec<ClickPoint>>(BufReader::new(file)) {
+    let backend = backend();
+    if let Some(contents) = backend.read("clickpoints.json") {
+        let parsed = contents.and_then(|c| {
+            serde_json::from_str::<Vec<ClickPoint>>(&c).map_err(|e| e.to_string())
+        });
+        
+        return match parsed {
+            Ok(points) => {
                 info!("Loaded {} click points from clickpoints.json", points.len());
-                return points;
+                Ok(points)
+            }
+            Err(e) => {
+                let msg = format!("Invalid {}: {}", backend.location("clickpoints.json"), e);
+                error!("{}", msg);
+                Err(msg)
+            }
+        };
+    }
+    
+    Ok(load_backup_points())
+}
+
+/// Load several point files in order and merge them by point name: a later
+/// file overrides the coordinates of a same-named point and appends new ones.
+/// A name repeated within one file stays a separate step; its n-th occurrence
+/// overrides the n-th same-named point merged so far. Files are read through
+/// the config backend (relative paths are inside the data directory);
+/// unreadable or missing files are logged and skipped.
+pub fn load_merged(paths: &[&Path]) -> Vec<ClickPoint> {
+    let backend = backend();
+    let mut merged: Vec<ClickPoint> = Vec::new();
+    
+    for path in paths {
+        let name = path.to_string_lossy();
+        let parsed = backend
+            .read(&name)
+            .unwrap_or_else(|| Err("file not found".to_string()))
+            .and_then(|c| serde_json::from_str::<Vec<ClickPoint>>(&c).map_err(|e| e.to_string()));
+        
+        let points = match parsed {
+            Ok(points) => points,
+            Err(e) => {
+                error!("Skipping {}: {}", backend.location(&name), e);
+                continue;
+            }
+        };
+        
+        let (mut replaced, mut added) = (0, 0);
+        let mut occurrences: HashMap<String, usize> = HashMap::new();
+        for point in points {
+            let seen = occurrences.entry(point.name.clone()).or_insert(0);
+            if *seen == 1 {
+                warn!("{} names more than one point {:?}; keeping them as separate steps", path.display(), point.name);
+            }
+            let slot = merged.iter_mut().filter(|p| p.name == point.name).nth(*seen);
+            *seen += 1;
+            match slot {
+                Some(existing) => {
+                    *existing = point;
+                    replaced += 1;
+                }
+                None => {
+                    merged.push(point);
+                    added += 1;
+                }
             }
         }
-        warn!("Failed to parse clickpoints.json");
+        info!("Merged {}: {} overridden, {} added", path.display(), replaced, added);
     }
     
-    // Fallback to confy configuration
-    match confy::load::<AppConfig>("SecCamCloud", None) {
+    merged
+}
+
+/// Points from the confy backup, or the built-in defaults (ignores clickpoints.json)
+pub fn load_backup_points() -> Vec<ClickPoint> {
+    // Fallback to confy configuration in the data directory
+    match backend().load_backup() {
         Ok(cfg) => {
             info!("Loaded {} click points from confy config", cfg.points.len());
             cfg.points
@@ -93,18 +550,95 @@ pub fn load_points() -> Vec<ClickPoint> {
     }
 }
 
-/// Save click points to configuration
+/// Write an autosave snapshot (compact JSON)
+pub fn save_autosave(autosave: &Autosave) -> Result<(), String> {
+    let json = serde_json::to_string(autosave).map_err(|e| e.to_string())?;
+    backend().write(AUTOSAVE_FILE, &json)
+}
+
+/// The autosave left by a previous session, if there is one and it is newer
+/// than clickpoints.json (i.e. holds edits that were never saved)
+pub fn load_autosave() -> Option<Autosave> {
+    let backend = backend();
+    let contents = match backend.read(AUTOSAVE_FILE)? {
+        Ok(contents) => contents,
+        Err(e) => {
+            warn!("Failed to read {}: {}", AUTOSAVE_FILE, e);
+            return None;
+        }
+    };
+
+    if let (Some(autosaved), Some(saved)) = (backend.modified(AUTOSAVE_FILE), backend.modified("clickpoints.json")) {
+        if autosaved <= saved {
+            info!("Ignoring {}: clickpoints.json is newer", AUTOSAVE_FILE);
+            return None;
+        }
+    }
+
+    match serde_json::from_str::<Autosave>(&contents) {
+        Ok(autosave) => Some(autosave),
+        Err(e) => {
+            warn!("Ignoring invalid {}: {}", AUTOSAVE_FILE, e);
+            None
+        }
+    }
+}
+
+/// Delete the autosave (after an explicit save or a declined restore)
+pub fn discard_autosave() {
+    if let Err(e) = backend().remove(AUTOSAVE_FILE) {
+        warn!("Failed to remove {}: {}", AUTOSAVE_FILE, e);
+    }
+}
+
+/// Load the confy backup from the data directory, falling back to confy's
+/// legacy default location for configs written by older versions
+fn load_confy() -> Result<AppConfig, confy::ConfyError> {
+    let path = paths::confy_path();
+    if path.exists() {
+        return confy::load_path(&path);
+    }
+
+    let legacy = confy::get_configuration_file_path(paths::CONFY_APP_NAME, None).ok();
+    if legacy.as_ref().is_some_and(|p| p.exists()) {
+        info!("Using legacy confy config: {}", legacy.unwrap().display());
+        return confy::load(paths::CONFY_APP_NAME, None);
+    }
+
+    // Creates the file with defaults
+    confy::load_path(&path)
+}
+
+/// Save click points to configuration (pretty-printed, in click order)
 pub fn save_points(points: &[ClickPoint]) {
+    save_points_with(points, SaveOptions::default());
+}
+
+/// Save click points with explicit formatting options
+pub fn save_points_with(points: &[ClickPoint], options: SaveOptions) {
     // Save to JSON file (preferred format)
-    let json_path = Path::new("clickpoints.json");
-    if let Ok(file) = File::create(json_path) {
-        if serde_json::to_writer_pretty(file, points).is_ok() {
-            info!("Saved {} click points to clickpoints.json", points.len());
-        } else {
-            warn!("Failed to serialize points to JSON");
-        }
+    let backend = backend();
+    let json = if options.sort_keys {
+        // serde_json::Value objects keep their keys sorted
+        serde_json::to_value(points).and_then(|value| {
+            if options.pretty {
+                serde_json::to_string_pretty(&value)
+            } else {
+                serde_json::to_string(&value)
+            }
+        })
+    } else if options.pretty {
+        serde_json::to_string_pretty(points)
     } else {
-        warn!("Failed to create clickpoints.json");
+        serde_json::to_string(points)
+    };
+    
+    match json {
+        Ok(json) => match backend.write("clickpoints.json", &json) {
+            Ok(()) => info!("Saved {} click points to clickpoints.json", points.len()),
+            Err(e) => warn!("Failed to write clickpoints.json: {}", e),
+        },
+        Err(_) => warn!("Failed to serialize points to JSON"),
     }
     
     // Also save to confy as backup
@@ -112,9 +646,160 @@ pub fn save_points(points: &[ClickPoint]) {
         points: points.to_vec(),
     };
     
-    if let Err(e) = confy::store("SecCamCloud", None, cfg) {
+    if let Err(e) = backend.store_backup(&cfg) {
         warn!("Failed to save confy config: {}", e);
     } else {
         info!("Saved backup config to confy");
     }
 }
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_points() -> Vec<ClickPoint> {
+        vec![
+            ClickPoint::new("Open", 10, 20),
+            ClickPoint::new("Confirm", 300, 400).with_delay(5),
+        ]
+    }
+
+    #[test]
+    fn save_then_load_round_trips() {
+        let backend = Rc::new(MemoryBackend::new());
+        let loaded = with_config_backend(backend.clone(), || {
+            save_points(&sample_points());
+            try_load_points()
+        });
+
+        assert_eq!(loaded, Ok(sample_points()));
+        assert!(backend.file("clickpoints.json").is_some());
+    }
+
+    #[test]
+    fn save_stores_backup() {
+        let backup = with_config_backend(Rc::new(MemoryBackend::new()), || {
+            save_points(&sample_points());
+            load_backup_points()
+        });
+
+        assert_eq!(backup, sample_points());
+    }
+
+    #[test]
+    fn compact_save_is_single_line() {
+        let backend = Rc::new(MemoryBackend::new());
+        with_config_backend(backend.clone(), || {
+            save_points_with(&sample_points(), SaveOptions { pretty: false, sort_keys: false });
+        });
+
+        let json = backend.file("clickpoints.json").unwrap();
+        assert!(!json.contains('\n'));
+    }
+
+    #[test]
+    fn default_save_is_indented_in_click_order() {
+        let backend = Rc::new(MemoryBackend::new());
+        with_config_backend(backend.clone(), || save_points(&sample_points()));
+
+        let json = backend.file("clickpoints.json").unwrap();
+        assert!(json.contains("\n  {"));
+        assert!(json.find("Open").unwrap() < json.find("Confirm").unwrap());
+    }
+
+    #[test]
+    fn sorted_keys_keep_click_order() {
+        let backend = Rc::new(MemoryBackend::new());
+        let loaded = with_config_backend(backend.clone(), || {
+            save_points_with(&sample_points(), SaveOptions::new().with_pretty(false).with_sort_keys(true));
+            try_load_points()
+        });
+
+        let json = backend.file("clickpoints.json").unwrap();
+        assert_eq!(
+            json,
+            r#"[{"name":"Open","x":10,"y":20},{"delay_override":5,"name":"Confirm","x":300,"y":400}]"#
+        );
+        assert_eq!(loaded, Ok(sample_points()));
+    }
+
+    #[test]
+    fn invalid_file_is_an_error_and_falls_back_to_defaults() {
+        let backend = Rc::new(MemoryBackend::new().with_file("clickpoints.json", "not json"));
+        let (strict, lenient) = with_config_backend(backend, || (try_load_points(), load_points()));
+
+        assert!(strict.is_err());
+        assert_eq!(lenient, DEFAULT_POINTS.clone());
+    }
+
+    #[test]
+    fn missing_files_use_defaults() {
+        let (points, hotkeys) = with_config_backend(Rc::new(MemoryBackend::new()), || {
+            (load_points(), load_hotkey_config())
+        });
+
+        assert_eq!(points, DEFAULT_POINTS.clone());
+        assert_eq!(hotkeys.emergency_key, DEFAULT_EMERGENCY_KEY);
+    }
+
+    #[test]
+    fn backend_is_restored_after_panic() {
+        let result = std::panic::catch_unwind(|| {
+            with_config_backend(Rc::new(MemoryBackend::new()), || panic!("test panic"))
+        });
+
+        assert!(result.is_err());
+        assert!(!backend().location("clickpoints.json").starts_with("memory:"));
+    }
+
+    fn merge(files: &[(&str, Vec<ClickPoint>)], order: &[&str]) -> Vec<ClickPoint> {
+        let backend = files.iter().fold(MemoryBackend::new(), |b, (name, points)| {
+            b.with_file(name, serde_json::to_string(points).unwrap())
+        });
+        let paths: Vec<&Path> = order.iter().map(Path::new).collect();
+        with_config_backend(Rc::new(backend), || load_merged(&paths))
+    }
+
+    #[test]
+    fn merge_overrides_and_appends_by_name() {
+        let merged = merge(
+            &[
+                ("base.json", sample_points()),
+                ("site.json", vec![ClickPoint::new("Confirm", 1, 2), ClickPoint::new("Close", 5, 6)]),
+            ],
+            &["base.json", "site.json"],
+        );
+
+        assert_eq!(
+            merged,
+            vec![ClickPoint::new("Open", 10, 20), ClickPoint::new("Confirm", 1, 2), ClickPoint::new("Close", 5, 6)]
+        );
+    }
+
+    #[test]
+    fn merge_skips_missing_and_invalid_files() {
+        let backend = MemoryBackend::new()
+            .with_file("base.json", serde_json::to_string(&sample_points()).unwrap())
+            .with_file("broken.json", "not json");
+        let paths = [Path::new("missing.json"), Path::new("base.json"), Path::new("broken.json")];
+        let merged = with_config_backend(Rc::new(backend), || load_merged(&paths));
+
+        assert_eq!(merged, sample_points());
+    }
+
+    #[test]
+    fn merge_keeps_repeated_names_as_separate_steps() {
+        let twice = vec![ClickPoint::new("Confirm", 1, 1), ClickPoint::new("Confirm", 2, 2)];
+        let merged = merge(
+            &[("base.json", twice), ("site.json", vec![ClickPoint::new("Confirm", 9, 9)])],
+            &["base.json", "site.json"],
+        );
+
+        // The override replaces the first occurrence only; both steps remain
+        assert_eq!(merged, vec![ClickPoint::new("Confirm", 9, 9), ClickPoint::new("Confirm", 2, 2)]);
+    }
+}
@@ -17,6 +17,8 @@ use simplelog::{ConfigBuilder, WriteLogger, TermLogger, TerminalMode, ColorChoic
 // MODULE DECLARATIONS
 // ============================================================================
 
+pub mod paths;
+pub mod util;
 pub mod config;
 pub mod watchdog;
 pub mod telemetry;
@@ -30,28 +32,44 @@ pub mod youtube;
 // ============================================================================
 
 // Configuration
-pub use config::{ClickPoint, AppConfig, DEFAULT_POINTS, load_points, save_points};
+pub use config::{
+    ClickPoint, ClickButton, ClickKind, StepAction, AppConfig, DEFAULT_POINTS, load_points, try_load_points, load_backup_points, load_merged, save_points, save_points_with, SaveOptions,
+    EmergencyAction, HotkeyConfig, DEFAULT_EMERGENCY_KEY, DEFAULT_PAUSE_KEY, DEFAULT_SCREENSHOT_KEY, load_hotkey_config,
+    ConfigBackend, FileBackend, MemoryBackend, with_config_backend,
+    Autosave, AUTOSAVE_FILE, save_autosave, load_autosave, discard_autosave,
+};
+
+// Paths
+pub use paths::{data_dir, data_file, set_data_dir, recordings_dir, DATA_DIR_ENV, RECORDINGS_DIR_ENV};
+
+// Utilities
+pub use util::{sanitize_filename, SimpleRng};
 
 // Watchdog
 pub use watchdog::WatchdogTimer;
 
 // Telemetry
-pub use telemetry::Telemetry;
+pub use telemetry::{Telemetry, TelemetryEvent, TelemetrySink, FileSink, CsvSink, JsonSink, TELEMETRY_LOG, TELEMETRY_CSV, TELEMETRY_JSONL};
 
 // Screenshot
-pub use screenshot::ScreenshotManager;
+pub use screenshot::{ScreenshotManager, ScreenshotConfig, DisplayInfo, ScreenSample, OverlapPolicy};
 
 // Automation
-pub use automation::{AutomationThread, AutomationMessage};
+pub use automation::{
+    AutomationThread, AutomationMessage, CalibrationReport, StepTiming,
+    render_template, render_template_with, format_date, validate_date_format, click_at, watchdog_timeout_sec, DEFAULT_TEXT_TEMPLATE, DEFAULT_DATE_FORMAT,
+    ScreenRect, SharedWindowBounds,
+};
 
 // Video Recording
-pub use vidrec::{VideoRecorder, VideoConfig, VideoFormat, CameraInfo, VideoMessage};
+pub use vidrec::{
+    VideoRecorder, VideoConfig, VideoFormat, CameraInfo, CameraMetadata, CameraProbe, RtspTransport, set_rtsp_transport, rtsp_transport, VideoMessage, OverlayCorner, OverlayTarget, RecordingStats,
+    CameraSource, decode_preview, DiscoveredCamera, discover_onvif,
+    MultiCameraRecorder, CameraSlot,
+};
 
 // YouTube Upload
-pub use youtube::{
-    YouTubeUploader, YouTubeCredentials, VideoMetadata, VideoPrivacy, VideoCategory,
-    VideoInfo, VideoValidator, UploadMessage, UploadStatus, BatchUploader,
-};
+pub use youtube::UploadError;
 
 // ============================================================================
 // PUBLIC CONSTANTS
@@ -75,6 +93,17 @@ pub fn is_windows() -> bool {
     cfg!(target_os = "windows")
 }
 
+/// Check whether a display server is reachable for the GUI. On Linux/BSD this
+/// means `DISPLAY` or `WAYLAND_DISPLAY` is set; other platforms always have one.
+pub fn display_available() -> bool {
+    if cfg!(any(target_os = "windows", target_os = "macos")) {
+        return true;
+    }
+    ["DISPLAY", "WAYLAND_DISPLAY"]
+        .iter()
+        .any(|var| std::env::var_os(var).is_some_and(|v| !v.is_empty()))
+}
+
 /// Check if a Windows virtual key is currently pressed
 #[cfg(target_os = "windows")]
 pub fn key_pressed(vk_code: i32) -> bool {
@@ -90,6 +119,21 @@ pub fn key_pressed(_vk_code: i32) -> bool {
     false
 }
 
+/// Optional compile-time features this build was made with
+pub fn active_features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    if cfg!(feature = "screenshots") {
+        features.push("screenshots");
+    }
+    if cfg!(feature = "video") {
+        features.push("video");
+    }
+    if cfg!(feature = "youtube") {
+        features.push("youtube");
+    }
+    features
+}
+
 // ============================================================================
 // LOG ROTATION
 // ============================================================================
@@ -111,30 +155,36 @@ fn rotate_logs() {
     }
     
     info!("Rotating log files (size: {} bytes)", meta.len());
-    
-    // Rotate backups: .3 -> delete, .2 -> .3, .1 -> .2, current -> .1
-    for i in (1..=MAX_LOG_BACKUPS).rev() {
-        let old_name = if i == 1 {
-            LOG_FILE.to_string()
-        } else {
-            format!("{}.{}", LOG_FILE, i - 1)
-        };
-        
-        let new_name = format!("{}.{}", LOG_FILE, i);
-        
-        if Path::new(&old_name).exists() {
-            if i == MAX_LOG_BACKUPS {
-                let _ = std::fs::remove_file(&old_name);
-            } else {
-                let _ = std::fs::rename(&old_name, &new_name);
-            }
-        }
-    }
+    rotate_file(LOG_FILE, MAX_LOG_BACKUPS);
     
     // Create fresh log file
     let _ = File::create(LOG_FILE);
 }
 
+/// Rename/delete operations that rotate `base` into `backups` numbered
+/// copies, in the order they must run: with 3 backups, `.3` is deleted, then
+/// `.2 -> .3`, `.1 -> .2` and `base -> .1`. With 0 backups `base` is deleted.
+pub fn rotation_plan(base: &str, backups: usize) -> Vec<(String, Option<String>)> {
+    let name = |i: usize| if i == 0 { base.to_string() } else { format!("{}.{}", base, i) };
+
+    let mut plan = vec![(name(backups), None)];
+    plan.extend((0..backups).rev().map(|i| (name(i), Some(name(i + 1)))));
+    plan
+}
+
+/// Execute [`rotation_plan`], skipping files that don't exist
+fn rotate_file(base: &str, backups: usize) {
+    for (from, to) in rotation_plan(base, backups) {
+        if !Path::new(&from).exists() {
+            continue;
+        }
+        let _ = match to {
+            Some(to) => std::fs::rename(&from, &to),
+            None => std::fs::remove_file(&from),
+        };
+    }
+}
+
 // ============================================================================
 // LOGGING INITIALIZATION
 // ============================================================================
@@ -180,6 +230,51 @@ pub fn setup_logging() {
     
     info!("========================================");
     info!("{} v{}", APP_TITLE, APP_VERSION);
+    let features = active_features();
+    if features.is_empty() {
+        info!("Features: none (build with --features screenshots,video,youtube)");
+    } else {
+        info!("Features: {}", features.join(", "));
+    }
     info!("Session started");
     info!("========================================");
 }
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(from: &str, to: Option<&str>) -> (String, Option<String>) {
+        (from.to_string(), to.map(str::to_string))
+    }
+
+    #[test]
+    fn rotation_plan_three_backups() {
+        assert_eq!(
+            rotation_plan("app.log", 3),
+            vec![
+                step("app.log.3", None),
+                step("app.log.2", Some("app.log.3")),
+                step("app.log.1", Some("app.log.2")),
+                step("app.log", Some("app.log.1")),
+            ]
+        );
+    }
+
+    #[test]
+    fn rotation_plan_one_backup() {
+        assert_eq!(
+            rotation_plan("app.log", 1),
+            vec![step("app.log.1", None), step("app.log", Some("app.log.1"))]
+        );
+    }
+
+    #[test]
+    fn rotation_plan_no_backups_deletes_base() {
+        assert_eq!(rotation_plan("app.log", 0), vec![step("app.log", None)]);
+    }
+}
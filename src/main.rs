@@ -6,25 +6,50 @@
 // License: GPLv2
 // ============================================================================
 
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
 use std::sync::{
     atomic::{AtomicBool, Ordering},
-    mpsc::{self, Sender},
-    Arc, Mutex,
+    mpsc::{self, Receiver, Sender},
+    Arc, Mutex, OnceLock,
 };
 use std::thread::{self, JoinHandle};
 use std::time::{Duration, Instant};
 
 use chrono::Local;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use eframe::egui;
-use global_hotkey::{GlobalHotKeyManager, GlobalHotKeyEvent, hotkey::{HotKey, Code, Modifiers}};
+use serde::Serialize;
+use global_hotkey::{GlobalHotKeyManager, GlobalHotKeyEvent, HotKeyState, hotkey::HotKey};
 
 use seccamcloud::{
-    setup_logging, load_points, save_points, ClickPoint, AutomationThread,
-    AutomationMessage, APP_TITLE, APP_VERSION,
+    setup_logging, active_features, display_available, load_backup_points, try_load_points, load_merged, save_points, Autosave, AUTOSAVE_FILE, save_autosave, load_autosave, discard_autosave, ClickPoint, ClickButton, ClickKind, StepAction, AutomationThread, DEFAULT_POINTS,
+    AutomationMessage, CalibrationReport, DEFAULT_TEXT_TEMPLATE, DEFAULT_DATE_FORMAT, format_date, APP_TITLE, APP_VERSION,
     Telemetry, ScreenshotManager,
+    EmergencyAction, HotkeyConfig, load_hotkey_config, click_at, watchdog_timeout_sec, set_data_dir, data_dir, data_file, recordings_dir,
+    LOG_FILE, paths, ScreenRect, SharedWindowBounds, RtspTransport, set_rtsp_transport,
+    TelemetrySink, FileSink, CsvSink, JsonSink, TELEMETRY_LOG, TELEMETRY_CSV, TELEMETRY_JSONL,
+    VideoRecorder, VideoConfig, CameraInfo, CameraSource, VideoMessage, decode_preview,
 };
 
+// ============================================================================
+// DEFAULTS
+// ============================================================================
+
+const DEFAULT_TOTAL_HOURS: i32 = 11;
+const DEFAULT_TOTAL_MINUTES: i32 = 30;
+const DEFAULT_STEP_DELAY: i32 = 10;
+const DEFAULT_MAX_RETRIES: i32 = 3;
+const DEFAULT_STEP4_WAIT: i32 = 10;
+/// Clicks this close together count as the "same" spot for the replay guard
+const REPLAY_GUARD_RADIUS_PX: i32 = 10;
+/// Bounds for --refresh-ms; slower than 1s makes the emergency stop key unreliable
+const REFRESH_MS_RANGE: std::ops::RangeInclusive<u64> = 16..=1000;
+/// Frames per second shown for --preview-camera
+const PREVIEW_FPS: f64 = 2.0;
+
 // ============================================================================
 // CLI ARGUMENTS
 // ============================================================================
@@ -35,58 +60,548 @@ use seccamcloud::{
 #[command(about = "Advanced automation tool with GUI, telemetry, and monitoring")]
 struct CliArgs {
     /// Enable dry-run mode (simulation without actual clicks)
-    #[arg(long, short = 'd')]
+    #[arg(long, short = 'd', global = true)]
     dry_run: bool,
 
+    /// Click point files merged by name in order (later files override earlier ones)
+    #[arg(long = "config", value_name = "FILE", num_args = 1..)]
+    config_files: Vec<PathBuf>,
+
+    /// Directory for configuration files (overrides SECCAM_DATA_DIR)
+    #[arg(long, value_name = "DIR", global = true)]
+    data_dir: Option<PathBuf>,
+
     /// Enable telemetry event logging
     #[arg(long, short = 't')]
     telemetry: bool,
 
+    /// Also write telemetry as CSV (logs/telemetry.csv) for spreadsheets
+    #[arg(long)]
+    telemetry_csv: bool,
+
     /// Enable screenshot capture (requires screenshots feature)
     #[arg(long, short = 's')]
     screenshots: bool,
+
+    /// Seconds of screen to record after an automation error (requires screenshots + video features)
+    #[arg(long, value_name = "SECONDS", default_value_t = 0)]
+    error_clip: u32,
+
+    /// Seconds between auto-saves of unsaved point edits while in edit mode (0 = off)
+    #[arg(long, value_name = "SECONDS", default_value_t = 30)]
+    autosave: u64,
+
+    /// Milliseconds between GUI refreshes while automation runs (16-1000, default 100).
+    /// Higher values use less CPU but update timers and check the stop key less often.
+    #[arg(long, value_name = "MS", default_value_t = 100)]
+    refresh_ms: u64,
+
+    /// Seed for humanized click offsets and delays, to reproduce a run (default: clock)
+    #[arg(long, value_name = "N")]
+    seed: Option<u64>,
+
+    /// RTSP transport for every camera stream (tcp or udp; default: FFmpeg's choice)
+    #[arg(long, value_name = "TRANSPORT", value_parser = ["tcp", "udp"])]
+    rtsp_transport: Option<String>,
+
+    /// Refuse to start if clickpoints.json exists but is invalid (instead of using defaults)
+    #[arg(long)]
+    strict_config: bool,
+
+    /// Refuse to start a run whose iteration limit and waits add up to more
+    /// than this many hours (0 = no cap)
+    #[arg(long, value_name = "HOURS", default_value_t = 0)]
+    max_runtime_hours: u32,
+
+    /// Show a live preview of a camera while the GUI runs: a webcam index or an
+    /// RTSP/HTTP URL (requires video feature)
+    #[arg(long, value_name = "SOURCE")]
+    preview_camera: Option<String>,
+
+    /// Print the effective configuration (paths, points, timing, features) and exit
+    #[arg(long)]
+    print_config: bool,
+
+    /// Machine-readable JSON output for --print-config and other reports
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run a click sequence from a JSON file once, then exit (no GUI)
+    RunSequence {
+        /// JSON file containing a list of click points
+        #[arg(long, short = 'f')]
+        file: PathBuf,
+
+        /// Seconds to wait after each click
+        #[arg(long, default_value_t = 10)]
+        step_delay: i32,
+
+        /// Attempts per click before failing
+        #[arg(long, default_value_t = 3)]
+        max_retries: i32,
+
+        /// Seconds for the long wait step
+        #[arg(long, default_value_t = 0)]
+        wait: i32,
+    },
+}
+
+// ============================================================================
+// COMMAND LINE MODE
+// ============================================================================
+
+/// Execute a sequence file once without the GUI, returning the process exit code
+fn run_sequence(file: &Path, dry_run: bool, step_delay: i32, max_retries: i32, wait: i32) -> i32 {
+    setup_logging();
+
+    let points: Vec<ClickPoint> = match File::open(file)
+        .map_err(|e| e.to_string())
+        .and_then(|f| serde_json::from_reader(BufReader::new(f)).map_err(|e| e.to_string()))
+    {
+        Ok(points) => points,
+        Err(e) => {
+            eprintln!("Failed to load {}: {}", file.display(), e);
+            return 2;
+        }
+    };
+
+    // Points without actions follow the fixed six-point choreography
+    let classic = points.iter().all(|p| p.action.is_none());
+    if classic && points.len() < DEFAULT_POINTS.len() {
+        eprintln!(
+            "{} defines {} points, but the sequence needs {}",
+            file.display(), points.len(), DEFAULT_POINTS.len()
+        );
+        return 2;
+    }
+
+    println!("Running {} steps from {}{}", points.len(), file.display(), if dry_run { " (dry run)" } else { "" });
+
+    // Progress is reported through the terminal logger; GUI messages are discarded
+    let (tx, _rx) = mpsc::channel();
+    let (_tx_stop, rx_stop) = mpsc::channel();
+
+    let thread = AutomationThread::new(
+        points,
+        wait,
+        step_delay,
+        max_retries,
+        0,
+        DEFAULT_DATE_FORMAT,
+        None,
+        dry_run,
+        tx,
+        rx_stop,
+        Arc::new(AtomicBool::new(false)),
+    );
+
+    match thread.run_once() {
+        Ok(()) => {
+            println!("Sequence completed");
+            0
+        }
+        Err(e) => {
+            eprintln!("Sequence failed: {}", e);
+            1
+        }
+    }
+}
+
+/// `--refresh-ms` clamped to the supported range
+fn refresh_interval(args: &CliArgs) -> Duration {
+    Duration::from_millis(args.refresh_ms.clamp(*REFRESH_MS_RANGE.start(), *REFRESH_MS_RANGE.end()))
+}
+
+/// Arm a recorder that only streams preview frames to `tx` (armed recorders
+/// never write a file)
+fn start_preview(location: &str, tx: Sender<VideoMessage>) -> Result<VideoRecorder, String> {
+    let camera = CameraInfo::new("Preview", CameraSource::parse(location));
+    let config = VideoConfig::new().with_preview(PREVIEW_FPS);
+    let mut recorder = VideoRecorder::new(camera, config).with_gui_sender(tx);
+    recorder.arm()?;
+    Ok(recorder)
+}
+
+/// Click an emergency cleanup sequence, logging each step to the GUI
+fn run_cleanup_clicks(points: &[ClickPoint], dry_run: bool, tx: &Sender<AutomationMessage>) {
+    for point in points {
+        let message = if dry_run {
+            format!("[DRY RUN] Would click {} at ({}, {})", point.name, point.x, point.y)
+        } else if let Err(e) = click_at(point) {
+            format!("ERROR: Cleanup click {} failed: {}", point.name, e)
+        } else {
+            format!("Cleanup click: {}", point.name)
+        };
+        let _ = tx.send(AutomationMessage::Log(message));
+        thread::sleep(Duration::from_millis(300));
+    }
+}
+
+/// Merge the `--config` files; falls back to the default points if none loaded
+fn load_config_files(files: &[PathBuf]) -> Vec<ClickPoint> {
+    // Command-line paths are relative to the working directory, not the data directory
+    let files: Vec<PathBuf> = files
+        .iter()
+        .map(|f| std::path::absolute(f).unwrap_or_else(|_| f.clone()))
+        .collect();
+    let paths: Vec<&Path> = files.iter().map(PathBuf::as_path).collect();
+    let points = load_merged(&paths);
+    if points.is_empty() {
+        DEFAULT_POINTS.clone()
+    } else {
+        points
+    }
+}
+
+/// Effective configuration reported by `--print-config`
+#[derive(Serialize)]
+struct EffectiveConfig {
+    version: &'static str,
+    paths: ConfigPaths,
+    features: Vec<&'static str>,
+    options: ConfigOptions,
+    timing: ConfigTiming,
+    points_source: String,
+    points: Vec<ClickPoint>,
+    emergency_key: String,
+    pause_key: String,
+    screenshot_key: String,
+    emergency_actions: Vec<EmergencyAction>,
+}
+
+#[derive(Serialize)]
+struct ConfigPaths {
+    data_dir: PathBuf,
+    clickpoints: PathBuf,
+    clickpoints_exists: bool,
+    autosave: PathBuf,
+    hotkeys: PathBuf,
+    confy_backup: PathBuf,
+    log_file: &'static str,
+    telemetry_log: &'static str,
+    telemetry_jsonl: &'static str,
+    telemetry_csv: Option<&'static str>,
+    screenshots_dir: &'static str,
+    recordings_dir: PathBuf,
+}
+
+#[derive(Serialize)]
+struct ConfigOptions {
+    dry_run: bool,
+    telemetry: bool,
+    screenshots: bool,
+    error_clip_sec: u32,
+    autosave_sec: u64,
+    refresh_ms: u64,
+    strict_config: bool,
+}
+
+#[derive(Serialize)]
+struct ConfigTiming {
+    total_hours: i32,
+    total_minutes: i32,
+    step_delay_sec: i32,
+    max_retries: i32,
+    step4_wait_sec: i32,
+    date_format: &'static str,
+    max_runtime_hours: u32,
+}
+
+impl EffectiveConfig {
+    /// Gather the configuration the app would start with. Contains no secrets:
+    /// credentials are never loaded here, only paths.
+    fn collect(args: &CliArgs) -> Self {
+        let clickpoints = data_file("clickpoints.json");
+        let clickpoints_exists = clickpoints.exists();
+
+        let (points, points_source) = if !args.config_files.is_empty() {
+            let files: Vec<String> = args.config_files.iter().map(|p| p.display().to_string()).collect();
+            (load_config_files(&args.config_files), format!("merged: {}", files.join(", ")))
+        } else {
+            match try_load_points() {
+                Ok(points) if clickpoints_exists => (points, "clickpoints.json".to_string()),
+                Ok(points) => (points, "confy backup / defaults".to_string()),
+                Err(e) => (load_backup_points(), format!("confy backup / defaults ({})", e)),
+            }
+        };
+        let hotkeys = load_hotkey_config();
+
+        Self {
+            version: APP_VERSION,
+            paths: ConfigPaths {
+                data_dir: data_dir().clone(),
+                clickpoints,
+                clickpoints_exists,
+                autosave: data_file(AUTOSAVE_FILE),
+                hotkeys: data_file("hotkeys.json"),
+                confy_backup: paths::confy_path(),
+                log_file: LOG_FILE,
+                telemetry_log: TELEMETRY_LOG,
+                telemetry_jsonl: TELEMETRY_JSONL,
+                telemetry_csv: args.telemetry_csv.then_some(TELEMETRY_CSV),
+                screenshots_dir: "screenshots",
+                recordings_dir: recordings_dir(),
+            },
+            features: active_features(),
+            options: ConfigOptions {
+                dry_run: args.dry_run,
+                telemetry: args.telemetry,
+                screenshots: args.screenshots,
+                error_clip_sec: args.error_clip,
+                autosave_sec: args.autosave,
+                refresh_ms: refresh_interval(&args).as_millis() as u64,
+                strict_config: args.strict_config,
+            },
+            timing: ConfigTiming {
+                total_hours: DEFAULT_TOTAL_HOURS,
+                total_minutes: DEFAULT_TOTAL_MINUTES,
+                step_delay_sec: DEFAULT_STEP_DELAY,
+                max_retries: DEFAULT_MAX_RETRIES,
+                step4_wait_sec: DEFAULT_STEP4_WAIT,
+                date_format: DEFAULT_DATE_FORMAT,
+                max_runtime_hours: args.max_runtime_hours,
+            },
+            points_source,
+            points,
+            emergency_key: hotkeys.emergency_key,
+            pause_key: hotkeys.pause_key,
+            screenshot_key: hotkeys.screenshot_key,
+            emergency_actions: hotkeys.emergency_actions,
+        }
+    }
+
+    fn print(&self) {
+        let yes_no = |b: bool| if b { "yes" } else { "no" };
+        let p = &self.paths;
+
+        println!("{} v{}", APP_TITLE, self.version);
+        println!();
+        println!("[paths]");
+        println!("data_dir        = {}", p.data_dir.display());
+        println!("clickpoints     = {}{}", p.clickpoints.display(), if p.clickpoints_exists { "" } else { " (missing)" });
+        println!("autosave        = {}", p.autosave.display());
+        println!("hotkeys         = {}", p.hotkeys.display());
+        println!("confy_backup    = {}", p.confy_backup.display());
+        println!("log_file        = {}", p.log_file);
+        println!("telemetry_log   = {}", p.telemetry_log);
+        println!("telemetry_jsonl = {}", p.telemetry_jsonl);
+        if let Some(csv) = p.telemetry_csv {
+            println!("telemetry_csv   = {}", csv);
+        }
+        println!("screenshots_dir = {}", p.screenshots_dir);
+        println!("recordings_dir  = {}", p.recordings_dir.display());
+
+        println!();
+        println!("[features]");
+        for feature in ["screenshots", "video", "youtube"] {
+            println!("{:<15} = {}", feature, yes_no(self.features.contains(&feature)));
+        }
+
+        println!();
+        println!("[options]");
+        println!("dry_run         = {}", yes_no(self.options.dry_run));
+        println!("telemetry       = {}", yes_no(self.options.telemetry));
+        println!("screenshots     = {}", yes_no(self.options.screenshots));
+        println!("error_clip_sec  = {}", self.options.error_clip_sec);
+        println!("autosave_sec    = {}", self.options.autosave_sec);
+        println!("refresh_ms      = {}", self.options.refresh_ms);
+        println!("strict_config   = {}", yes_no(self.options.strict_config));
+
+        println!();
+        println!("[timing]");
+        println!("total_wait      = {}h {}m", self.timing.total_hours, self.timing.total_minutes);
+        println!("step_delay_sec  = {}", self.timing.step_delay_sec);
+        println!("max_retries     = {}", self.timing.max_retries);
+        println!("step4_wait_sec  = {}", self.timing.step4_wait_sec);
+        println!("date_format     = {}", self.timing.date_format);
+        println!("max_runtime_h   = {}", self.timing.max_runtime_hours);
+
+        println!();
+        println!("[points] source = {}", self.points_source);
+        for (i, p) in self.points.iter().enumerate() {
+            println!("{:>2}. {:<24} ({}, {})", i + 1, p.name, p.x, p.y);
+        }
+
+        println!();
+        println!("[hotkeys]");
+        println!("emergency_key   = {}", self.emergency_key);
+        println!("pause_key       = {}", self.pause_key);
+        println!("screenshot_key  = {}", self.screenshot_key);
+
+        println!();
+        println!("[emergency_actions]");
+        for action in &self.emergency_actions {
+            println!("- {:?}", action);
+        }
+    }
+}
+
+/// Print the effective configuration as text, or as JSON with `--json`
+fn print_config(args: &CliArgs) -> i32 {
+    let config = EffectiveConfig::collect(args);
+
+    if args.json {
+        match serde_json::to_string_pretty(&config) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("Failed to serialize configuration: {}", e);
+                return 1;
+            }
+        }
+    } else {
+        config.print();
+    }
+
+    0
 }
 
 // ============================================================================
 // HOTKEY MONITOR
 // ============================================================================
 
+/// What a global hotkey does when pressed
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum HotkeyAction {
+    EmergencyStop,
+    TogglePause,
+    Screenshot,
+}
+
+impl HotkeyAction {
+    fn label(self) -> &'static str {
+        match self {
+            HotkeyAction::EmergencyStop => "Emergency Stop",
+            HotkeyAction::TogglePause => "Pause/Resume",
+            HotkeyAction::Screenshot => "Screenshot",
+        }
+    }
+}
+
+/// One registered key: the key text from hotkeys.json, its hotkey id, and
+/// whether it is currently held (so OS key repeat fires only once)
+struct HotkeyBinding {
+    key: String,
+    id: u32,
+    action: HotkeyAction,
+    held: bool,
+}
+
 struct HotkeyMonitor {
-    manager: GlobalHotKeyManager,
-    hotkey: HotKey,
+    /// None if no hotkey manager could be created
+    manager: Option<GlobalHotKeyManager>,
+    bindings: Vec<(HotKey, HotkeyBinding)>,
+    events: mpsc::Receiver<GlobalHotKeyEvent>,
+    /// Woken on every hotkey event so presses are handled while the GUI is idle
+    waker: Arc<OnceLock<egui::Context>>,
 }
 
 impl HotkeyMonitor {
-    fn new() -> Result<Self, String> {
-        let manager = GlobalHotKeyManager::new()
-            .map_err(|e| format!("Failed to create hotkey manager: {}", e))?;
-        
-        // Register DELETE key
-        let hotkey = HotKey::new(None, Code::Delete);
-        
-        manager.register(hotkey)
-            .map_err(|e| format!("Failed to register DELETE hotkey: {}", e))?;
-        
-        Ok(Self {
-            manager,
-            hotkey,
-        })
-    }
-
-    fn check_emergency_stop(&self) -> bool {
-        // Check for hotkey events
-        if let Ok(event) = GlobalHotKeyEvent::receiver().try_recv() {
-            if event.id == self.hotkey.id() {
-                return true;
+    /// Register the keys from `config`. Empty keys are skipped; keys that fail
+    /// to parse or register are reported and left out.
+    fn new(config: &HotkeyConfig) -> Self {
+        let (tx, events) = mpsc::channel();
+        let waker: Arc<OnceLock<egui::Context>> = Arc::new(OnceLock::new());
+        let wake = waker.clone();
+        GlobalHotKeyEvent::set_event_handler(Some(move |event| {
+            let _ = tx.send(event);
+            if let Some(ctx) = wake.get() {
+                ctx.request_repaint();
+            }
+        }));
+
+        let mut monitor = Self {
+            manager: None,
+            bindings: Vec::new(),
+            events,
+            waker,
+        };
+
+        let manager = match GlobalHotKeyManager::new() {
+            Ok(manager) => manager,
+            Err(e) => {
+                eprintln!("Warning: Failed to create hotkey manager: {}", e);
+                eprintln!("Hotkeys (including emergency stop) will not be available");
+                return monitor;
+            }
+        };
+
+        let table = [
+            (&config.emergency_key, HotkeyAction::EmergencyStop),
+            (&config.pause_key, HotkeyAction::TogglePause),
+            (&config.screenshot_key, HotkeyAction::Screenshot),
+        ];
+        for (key, action) in table {
+            if key.is_empty() {
+                continue;
+            }
+            let registered = key
+                .parse::<HotKey>()
+                .map_err(|e| format!("Invalid {} key \"{}\": {}", action.label(), key, e))
+                .and_then(|hotkey| {
+                    manager.register(hotkey)
+                        .map(|()| hotkey)
+                        .map_err(|e| format!("Failed to register {} hotkey: {}", key, e))
+                });
+            match registered {
+                Ok(hotkey) => monitor.bindings.push((hotkey, HotkeyBinding {
+                    key: key.clone(),
+                    id: hotkey.id(),
+                    action,
+                    held: false,
+                })),
+                Err(e) => {
+                    eprintln!("Warning: {}", e);
+                    eprintln!("{} hotkey will not be available", action.label());
+                }
             }
         }
-        false
+
+        monitor.manager = Some(manager);
+        monitor
+    }
+
+    /// Repaint `ctx` whenever a hotkey event arrives
+    fn wake_on_event(&self, ctx: &egui::Context) {
+        let _ = self.waker.set(ctx.clone());
+    }
+
+    /// Registered keys with their actions, in table order
+    fn active_keys(&self) -> impl Iterator<Item = (&str, HotkeyAction)> {
+        self.bindings.iter().map(|(_, b)| (b.key.as_str(), b.action))
+    }
+
+    /// Actions for keys pressed since the last poll, once per press. Doesn't
+    /// allocate unless a key was pressed.
+    fn poll(&mut self) -> Vec<HotkeyAction> {
+        let mut actions = Vec::new();
+        while let Ok(event) = self.events.try_recv() {
+            let Some((_, binding)) = self.bindings.iter_mut().find(|(_, b)| b.id == event.id) else {
+                continue;
+            };
+            let pressed = event.state == HotKeyState::Pressed;
+            if pressed && !binding.held {
+                actions.push(binding.action);
+            }
+            binding.held = pressed;
+        }
+        actions
     }
 }
 
 impl Drop for HotkeyMonitor {
     fn drop(&mut self) {
-        let _ = self.manager.unregister(self.hotkey);
+        if let Some(manager) = &self.manager {
+            for (hotkey, _) in &self.bindings {
+                let _ = manager.unregister(*hotkey);
+            }
+        }
+        GlobalHotKeyEvent::set_event_handler(None::<fn(GlobalHotKeyEvent)>);
     }
 }
 
@@ -94,12 +609,24 @@ impl Drop for HotkeyMonitor {
 // APPLICATION STATE
 // ============================================================================
 
+/// How a launch runs the sequence
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RunMode {
+    /// Loop until stopped (or the "Stop After" limit)
+    Continuous,
+    /// Exactly one iteration, then stop
+    Once,
+    /// One measuring pass, see `AutomationThread::with_calibration`
+    Calibrate,
+}
+
 struct AppState {
     // Thread management
     automation_thread: Option<JoinHandle<()>>,
     stop_sender: Option<Sender<()>>,
     message_receiver: Arc<Mutex<mpsc::Receiver<AutomationMessage>>>,
     stop_flag: Arc<AtomicBool>,
+    pause_flag: Arc<AtomicBool>,
 
     // Configuration
     points: Vec<ClickPoint>,
@@ -108,7 +635,30 @@ struct AppState {
     step_delay: i32,
     max_retries: i32,
     step4_wait: i32,
+    text_template: String,
+    date_format: String,
+    limit_iterations: bool,
+    max_iterations: u32,
+    /// Cap on the estimated length of a limited run (0 = none)
+    max_runtime_hours: u32,
+    verify_text: bool,
     dry_run: bool,
+    idle_cpu_gate: bool,
+    idle_cpu_threshold: i32,
+    idle_cpu_timeout: i32,
+    max_clicks_per_minute: i32,
+    error_clip_sec: u32,
+    start_delay: i32,
+    self_click_guard: bool,
+    capture_each_step: bool,
+    screenshot_settle_ms: u64,
+    humanize: bool,
+    humanize_seed: Option<u64>,
+    replay_guard_ms: u64,
+    focus_click: bool,
+    focus_point: ClickPoint,
+    wait_milestone_min: i32,
+    window_bounds: SharedWindowBounds,
 
     // GUI state
     log_messages: Vec<String>,
@@ -116,68 +666,228 @@ struct AppState {
     time_remaining: i32,
     running: bool,
     edit_mode: bool,
+    calibration: Option<CalibrationReport>,
+    /// clickpoints.json parse error shown in a dialog until dismissed
+    config_error: Option<String>,
+    /// Unsaved edits from a previous session, offered for restore until answered
+    autosave_offer: Option<Autosave>,
+    autosave_interval: Option<Duration>,
+    last_autosave: Instant,
+    /// Points and timing as last loaded or saved; autosave only runs when they differ
+    saved_edits: Autosave,
+    /// Repaint interval while automation runs; idle repaints only on input
+    refresh_interval: Duration,
+    shut_down: bool,
 
     // Statistics
     iterations: u32,
     start_time: Option<Instant>,
 
     // Components
+    emergency_actions: Vec<EmergencyAction>,
     telemetry: Arc<Telemetry>,
     screenshots: Arc<ScreenshotManager>,
     hotkeys: HotkeyMonitor,
     gui_sender: Sender<AutomationMessage>,
+    /// Armed recorder behind --preview-camera
+    preview_recorder: Option<VideoRecorder>,
+    video_receiver: Receiver<VideoMessage>,
+    /// Latest preview frame per camera
+    previews: BTreeMap<String, egui::TextureHandle>,
 }
 
 impl AppState {
     fn new(args: CliArgs) -> Self {
         setup_logging();
 
-        let points = load_points();
+        // A broken clickpoints.json is shown to the user instead of silently defaulting
+        let (points, config_error) = if args.config_files.is_empty() {
+            match try_load_points() {
+                Ok(points) => (points, None),
+                Err(e) => (load_backup_points(), Some(e)),
+            }
+        } else {
+            (load_config_files(&args.config_files), None)
+        };
+        let autosave_offer = args.config_files.is_empty().then(load_autosave).flatten();
         let (tx, rx) = mpsc::channel();
-        let telemetry = Telemetry::new(args.telemetry);
+        let mut sinks: Vec<Box<dyn TelemetrySink>> = vec![Box::new(FileSink), Box::new(JsonSink)];
+        if args.telemetry && args.telemetry_csv {
+            match CsvSink::new(TELEMETRY_CSV) {
+                Ok(sink) => sinks.push(Box::new(sink)),
+                Err(e) => eprintln!("Warning: {}", e),
+            }
+        }
+        let telemetry = Telemetry::with_sinks(args.telemetry, sinks);
         let screenshots = ScreenshotManager::new(args.screenshots);
+        let hotkey_config = load_hotkey_config();
 
         telemetry.log("Application started");
 
-        Self {
+        let (video_tx, video_rx) = mpsc::channel();
+        let preview = args.preview_camera.as_deref().map(|location| start_preview(location, video_tx));
+
+        let saved_edits = Autosave {
+            saved_at: String::new(),
+            points: points.clone(),
+            total_hours: DEFAULT_TOTAL_HOURS,
+            total_minutes: DEFAULT_TOTAL_MINUTES,
+            step_delay: DEFAULT_STEP_DELAY,
+            max_retries: DEFAULT_MAX_RETRIES,
+            step4_wait: DEFAULT_STEP4_WAIT,
+            date_format: DEFAULT_DATE_FORMAT.to_string(),
+        };
+
+        let mut state = Self {
             automation_thread: None,
             stop_sender: None,
             message_receiver: Arc::new(Mutex::new(rx)),
             stop_flag: Arc::new(AtomicBool::new(false)),
+            pause_flag: Arc::new(AtomicBool::new(false)),
             points,
-            total_hours: 11,
-            total_minutes: 30,
-            step_delay: 10,
-            max_retries: 3,
-            step4_wait: 10,
+            total_hours: DEFAULT_TOTAL_HOURS,
+            total_minutes: DEFAULT_TOTAL_MINUTES,
+            step_delay: DEFAULT_STEP_DELAY,
+            max_retries: DEFAULT_MAX_RETRIES,
+            step4_wait: DEFAULT_STEP4_WAIT,
+            text_template: DEFAULT_TEXT_TEMPLATE.to_string(),
+            date_format: DEFAULT_DATE_FORMAT.to_string(),
+            limit_iterations: false,
+            max_iterations: 5,
+            max_runtime_hours: args.max_runtime_hours,
+            verify_text: false,
             dry_run: args.dry_run,
+            idle_cpu_gate: false,
+            idle_cpu_threshold: 20,
+            idle_cpu_timeout: 30,
+            max_clicks_per_minute: 0,
+            error_clip_sec: args.error_clip,
+            start_delay: 0,
+            self_click_guard: false,
+            capture_each_step: false,
+            screenshot_settle_ms: 0,
+            humanize: false,
+            humanize_seed: args.seed,
+            replay_guard_ms: 0,
+            focus_click: false,
+            focus_point: ClickPoint::new("Focus", 0, 0),
+            wait_milestone_min: 15,
+            window_bounds: Arc::new(Mutex::new(None)),
             log_messages: Vec::new(),
             status: "Status: Ready".to_string(),
             time_remaining: 0,
             running: false,
             edit_mode: false,
+            calibration: None,
+            config_error,
+            autosave_offer,
+            autosave_interval: (args.autosave > 0).then(|| Duration::from_secs(args.autosave)),
+            refresh_interval: refresh_interval(&args),
+            last_autosave: Instant::now(),
+            saved_edits,
+            shut_down: false,
             iterations: 0,
             start_time: None,
+            emergency_actions: hotkey_config.emergency_actions,
             telemetry,
             screenshots,
-            hotkeys: HotkeyMonitor::new().unwrap_or_else(|e| {
-                eprintln!("Warning: Failed to setup hotkey: {}", e);
-                eprintln!("Emergency stop (DELETE key) will not be available");
-                // Return a dummy that always returns false
-                HotkeyMonitor {
-                    manager: GlobalHotKeyManager::new().unwrap(),
-                    hotkey: HotKey::new(None, Code::Delete),
-                }
-            }),
+            hotkeys: HotkeyMonitor::new(&hotkey_config),
             gui_sender: tx,
+            preview_recorder: None,
+            video_receiver: video_rx,
+            previews: BTreeMap::new(),
+        };
+
+        match preview {
+            Some(Ok(recorder)) => state.preview_recorder = Some(recorder),
+            Some(Err(e)) => state.add_log(&format!("Camera preview failed: {}", e)),
+            None => {}
         }
+        state
     }
 
     fn start_automation(&mut self) {
+        self.launch(RunMode::Continuous);
+    }
+
+    fn run_once(&mut self) {
+        self.launch(RunMode::Once);
+    }
+
+    fn start_calibration(&mut self) {
+        self.launch(RunMode::Calibrate);
+    }
+
+    /// Check that the timing settings make sense together.
+    /// Returns (errors that block the start, warnings).
+    fn validate_timing(&self) -> (Vec<String>, Vec<String>) {
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+        let watchdog = watchdog_timeout_sec(self.max_retries);
+
+        // These waits happen mid-click while the watchdog is armed
+        if self.max_clicks_per_minute > 0 {
+            let interval = 60.0 / self.max_clicks_per_minute as f64;
+            if interval >= watchdog as f64 {
+                errors.push(format!(
+                    "Max clicks/min {} spaces clicks {:.0}s apart, which trips the {}s watchdog - allow at least {} clicks/min",
+                    self.max_clicks_per_minute, interval, watchdog, 60 / watchdog + 1
+                ));
+            }
+        }
+        if self.replay_guard_ms >= watchdog * 1000 {
+            errors.push(format!(
+                "Repeat guard of {}ms is longer than the {}s watchdog",
+                self.replay_guard_ms, watchdog
+            ));
+        }
+
+        let total_seconds = self.total_hours * 3600 + self.total_minutes * 60;
+        if self.limit_iterations && self.max_runtime_hours > 0 {
+            let step_waits: i64 = self.points
+                .iter()
+                .map(|p| p.delay_override.map_or(self.step_delay, |d| d.max(0)) as i64)
+                .sum();
+            let per_iteration = step_waits + self.step4_wait as i64 + total_seconds as i64;
+            let estimate = per_iteration * self.max_iterations as i64;
+            if estimate > self.max_runtime_hours as i64 * 3600 {
+                errors.push(format!(
+                    "{} iterations of ~{}s each take about {:.1}h, over the {}h runtime cap",
+                    self.max_iterations, per_iteration, estimate as f64 / 3600.0, self.max_runtime_hours
+                ));
+            }
+        }
+        if total_seconds == 0 && self.step_delay == 0 && self.step4_wait == 0 {
+            warnings.push("All waits are 0 - the sequence will repeat back-to-back".to_string());
+        }
+        if total_seconds > 0 && total_seconds < self.step_delay * self.points.len() as i32 {
+            warnings.push(format!(
+                "Long wait ({}s) is shorter than the step delays of one iteration ({}s)",
+                total_seconds,
+                self.step_delay * self.points.len() as i32
+            ));
+        }
+
+        (errors, warnings)
+    }
+
+    fn launch(&mut self, mode: RunMode) {
         if self.running {
             return;
         }
 
+        let (errors, warnings) = self.validate_timing();
+        for warning in &warnings {
+            self.add_log(&format!("⚠ {}", warning));
+        }
+        if !errors.is_empty() {
+            for error in &errors {
+                self.add_log(&format!("ERROR: {}", error));
+            }
+            self.status = "Status: Invalid timing settings".to_string();
+            return;
+        }
+
         self.running = true;
         self.iterations = 0;
         self.start_time = Some(Instant::now());
@@ -188,28 +898,80 @@ impl AppState {
         let (tx_stop, rx_stop) = mpsc::channel();
         self.stop_sender = Some(tx_stop);
 
-        let thread = AutomationThread::new(
+        let mut thread = AutomationThread::new(
             self.points.clone(),
             total_seconds,
             self.step_delay,
             self.max_retries,
             self.step4_wait,
+            self.date_format.clone(),
+            match mode {
+                RunMode::Once => Some(1),
+                _ => self.limit_iterations.then_some(self.max_iterations),
+            },
             self.dry_run,
             self.gui_sender.clone(),
             rx_stop,
             self.stop_flag.clone(),
         );
 
-        self.telemetry.log(format!(
-            "START: {}h{}m, retries={}, dry_run={}",
-            self.total_hours, self.total_minutes, self.max_retries, self.dry_run
-        ));
+        thread = thread
+            .with_screenshots(self.screenshots.clone())
+            .with_telemetry(self.telemetry.clone())
+            .with_text_template(self.text_template.clone())
+            .with_text_verification(self.verify_text)
+            .with_max_clicks_per_minute(self.max_clicks_per_minute as u32)
+            .with_error_clip(self.error_clip_sec)
+            .with_start_delay(self.start_delay)
+            .with_step_screenshots(self.capture_each_step)
+            .with_screenshot_settle(self.screenshot_settle_ms)
+            .with_replay_guard(self.replay_guard_ms, REPLAY_GUARD_RADIUS_PX)
+            .with_wait_milestones(self.wait_milestone_min * 60);
+
+        self.pause_flag.store(false, Ordering::SeqCst);
+        thread = thread.with_pause_flag(self.pause_flag.clone());
+
+        if self.humanize {
+            thread = thread.with_humanize(self.humanize_seed);
+        }
+
+        if self.focus_click {
+            thread = thread.with_focus_point(self.focus_point.clone());
+        }
+
+        if self.self_click_guard {
+            thread = thread.with_self_click_guard(self.window_bounds.clone());
+        }
+
+        if self.idle_cpu_gate {
+            thread = thread.with_idle_cpu_gate(self.idle_cpu_threshold as f32, self.idle_cpu_timeout);
+        }
+
+        if mode == RunMode::Calibrate {
+            thread = thread.with_calibration();
+        }
+
+        let text = format!(
+            "START: {}h{}m, retries={}, dry_run={}, mode={:?}",
+            self.total_hours, self.total_minutes, self.max_retries, self.dry_run, mode
+        );
+        self.telemetry.event_with_text("START", &[
+            ("hours", &self.total_hours.to_string()),
+            ("minutes", &self.total_minutes.to_string()),
+            ("retries", &self.max_retries.to_string()),
+            ("dry_run", &self.dry_run.to_string()),
+            ("mode", &format!("{:?}", mode)),
+        ], Some(&text));
 
         self.automation_thread = Some(thread::spawn(move || {
             thread.run();
         }));
 
-        self.add_log("Automation started");
+        self.add_log(match mode {
+            RunMode::Continuous => "Automation started",
+            RunMode::Once => "Single run started",
+            RunMode::Calibrate => "Calibration started",
+        });
     }
 
     fn stop_automation(&mut self) {
@@ -219,6 +981,7 @@ impl AppState {
 
         self.running = false;
         self.stop_flag.store(true, Ordering::SeqCst);
+        self.pause_flag.store(false, Ordering::SeqCst);
 
         if let Some(sender) = self.stop_sender.take() {
             let _ = sender.send(());
@@ -230,16 +993,129 @@ impl AppState {
 
         if let Some(start) = self.start_time {
             let duration = start.elapsed().as_secs_f64();
-            self.telemetry.log(format!(
-                "COMPLETE: duration={:.1}s, iterations={}",
-                duration, self.iterations
-            ));
+            let text = format!("COMPLETE: duration={:.1}s, iterations={}", duration, self.iterations);
+            self.telemetry.event_with_text("COMPLETE", &[
+                ("duration_sec", &format!("{:.1}", duration)),
+                ("iterations", &self.iterations.to_string()),
+            ], Some(&text));
         }
 
         self.add_log("Automation stopped");
         self.status = "Status: Stopped".to_string();
     }
 
+    fn is_paused(&self) -> bool {
+        self.pause_flag.load(Ordering::SeqCst)
+    }
+
+    /// Pause or resume the running sequence (takes effect at the next second
+    /// of a wait or before the next step)
+    fn toggle_pause(&mut self) {
+        if !self.running {
+            return;
+        }
+        let paused = !self.is_paused();
+        self.pause_flag.store(paused, Ordering::SeqCst);
+        self.telemetry.event(if paused { "PAUSE" } else { "RESUME" }, &[]);
+        if paused {
+            self.status = "Status: Paused".to_string();
+        }
+    }
+
+    fn run_hotkey_action(&mut self, action: HotkeyAction) {
+        match action {
+            HotkeyAction::EmergencyStop if self.running => self.run_emergency_actions(),
+            HotkeyAction::EmergencyStop => {}
+            HotkeyAction::TogglePause => self.toggle_pause(),
+            HotkeyAction::Screenshot => match self.screenshots.capture("hotkey", "manual") {
+                Some(path) => self.add_log(&format!("Screenshot: {}", path)),
+                None => self.add_log("Screenshot not captured (screenshots disabled or unavailable)"),
+            },
+        }
+    }
+
+    /// Run the configured emergency actions in order. Cleanup clicks pause
+    /// between points, so each click list runs on a worker thread that
+    /// reports back through the message channel.
+    fn run_emergency_actions(&mut self) {
+        self.add_log("EMERGENCY STOP TRIGGERED");
+
+        for action in self.emergency_actions.clone() {
+            match action {
+                EmergencyAction::StopAutomation => {
+                    self.stop_automation();
+                }
+                EmergencyAction::Screenshot => {
+                    match self.screenshots.capture("emergency", "stop") {
+                        Some(path) => self.add_log(&format!("Emergency screenshot: {}", path)),
+                        None => self.add_log("Emergency screenshot not captured"),
+                    }
+                }
+                EmergencyAction::FlushTelemetry => {
+                    self.telemetry.log("EMERGENCY STOP");
+                    self.telemetry.flush_summary();
+                }
+                EmergencyAction::Click { points } => {
+                    let tx = self.gui_sender.clone();
+                    let dry_run = self.dry_run;
+                    thread::spawn(move || run_cleanup_clicks(&points, dry_run, &tx));
+                }
+            }
+        }
+    }
+
+    /// Tear down every subsystem in a fixed order: automation (which joins its
+    /// watchdog), the camera preview, then telemetry. The GUI starts no other
+    /// recorders; apps embedding `MultiCameraRecorder` call its `stop_all`
+    /// themselves. Safe to call twice.
+    fn shutdown(&mut self) {
+        if self.shut_down {
+            return;
+        }
+        self.shut_down = true;
+
+        // 1. Automation thread - joining it also drops and joins its watchdog
+        self.stop_automation();
+        self.discard_autosave_if_unchanged();
+
+        // 2. Camera preview - releases the camera
+        if let Some(mut recorder) = self.preview_recorder.take() {
+            let _ = recorder.disarm();
+        }
+
+        // 3. Telemetry - session summary, then the last event of the session
+        self.telemetry.flush_summary();
+        self.telemetry.log("Application exiting");
+        log::info!("Shutdown complete");
+    }
+
+    /// Handle recorder messages; preview frames become (or replace) a texture
+    fn process_video_messages(&mut self, ctx: &egui::Context) {
+        while let Ok(msg) = self.video_receiver.try_recv() {
+            match msg {
+                VideoMessage::PreviewFrame { camera, jpeg } => {
+                    let image = match decode_preview(&jpeg) {
+                        Ok((size, rgb)) => egui::ColorImage::from_rgb(size, &rgb),
+                        Err(e) => {
+                            log::warn!("Dropping preview frame from {}: {}", camera, e);
+                            continue;
+                        }
+                    };
+                    match self.previews.get_mut(&camera) {
+                        Some(texture) => texture.set(image, egui::TextureOptions::LINEAR),
+                        None => {
+                            let texture = ctx.load_texture(format!("preview-{}", camera), image, egui::TextureOptions::LINEAR);
+                            self.previews.insert(camera, texture);
+                        }
+                    }
+                }
+                VideoMessage::Log(text) => self.add_log(&text),
+                VideoMessage::Error(text) => self.add_log(&format!("ERROR: {}", text)),
+                _ => {}
+            }
+        }
+    }
+
     fn process_messages(&mut self) {
         let receiver = self.message_receiver.lock().unwrap();
 
@@ -257,6 +1133,13 @@ impl AppState {
                 AutomationMessage::ErrorPopup(text) => {
                     self.add_log(&format!("ERROR: {}", text));
                 }
+                AutomationMessage::Calibration(report) => {
+                    self.telemetry.event("CALIBRATION", &[
+                        ("step_delay_sec", &report.suggested_step_delay.to_string()),
+                        ("step4_wait_sec", &report.suggested_step4_wait.to_string()),
+                    ]);
+                    self.calibration = Some(report);
+                }
                 AutomationMessage::Stop => {
                     self.running = false;
                 }
@@ -283,11 +1166,230 @@ impl AppState {
         }
     }
 
+    fn export_log(&mut self) {
+        let default_name = format!("activity_log_{}.txt", Local::now().format("%Y%m%d_%H%M%S"));
+
+        let Some(path) = rfd::FileDialog::new()
+            .set_file_name(&default_name)
+            .add_filter("Text", &["txt", "log"])
+            .add_filter("JSON", &["json"])
+            .save_file()
+        else {
+            return;
+        };
+
+        let is_json = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+
+        let content = if is_json {
+            serde_json::to_string_pretty(&self.log_messages).map_err(|e| e.to_string())
+        } else {
+            Ok(self.log_messages.join("\n") + "\n")
+        };
+
+        match content.and_then(|c| std::fs::write(&path, c).map_err(|e| e.to_string())) {
+            Ok(()) => {
+                self.telemetry.log(format!("Log exported to {}", path.display()));
+                self.add_log(&format!("Log exported to {}", path.display()));
+            }
+            Err(e) => {
+                self.add_log(&format!("ERROR: Failed to export log: {}", e));
+            }
+        }
+    }
+
     fn save_points(&mut self) {
         save_points(&self.points);
+        discard_autosave();
+        self.saved_edits = self.edit_snapshot();
         self.telemetry.log("Configuration saved");
         self.add_log("Points saved");
     }
+
+    /// Snapshot points and timing to the autosave file if the interval has passed
+    fn autosave_if_due(&mut self) {
+        let Some(interval) = self.autosave_interval else {
+            return;
+        };
+        if !self.edit_mode || self.last_autosave.elapsed() < interval {
+            return;
+        }
+        self.last_autosave = Instant::now();
+
+        let autosave = self.edit_snapshot();
+        if autosave == self.saved_edits {
+            return;
+        }
+        let autosave = Autosave {
+            saved_at: Local::now().to_rfc3339(),
+            ..autosave
+        };
+        if let Err(e) = save_autosave(&autosave) {
+            self.add_log(&format!("ERROR: Auto-save failed: {}", e));
+        }
+    }
+
+    /// Current points and timing, without a timestamp so snapshots compare equal
+    fn edit_snapshot(&self) -> Autosave {
+        Autosave {
+            saved_at: String::new(),
+            points: self.points.clone(),
+            total_hours: self.total_hours,
+            total_minutes: self.total_minutes,
+            step_delay: self.step_delay,
+            max_retries: self.max_retries,
+            step4_wait: self.step4_wait,
+            date_format: self.date_format.clone(),
+        }
+    }
+
+    /// Drop the autosave file when nothing differs from the last saved state
+    fn discard_autosave_if_unchanged(&self) {
+        if self.edit_snapshot() == self.saved_edits {
+            discard_autosave();
+        }
+    }
+
+    fn restore_autosave(&mut self, autosave: Autosave) {
+        self.points = autosave.points;
+        self.total_hours = autosave.total_hours;
+        self.total_minutes = autosave.total_minutes;
+        self.step_delay = autosave.step_delay;
+        self.max_retries = autosave.max_retries;
+        self.step4_wait = autosave.step4_wait;
+        self.date_format = autosave.date_format;
+        self.edit_mode = true;
+        self.telemetry.log("Auto-saved edits restored");
+        self.add_log(&format!("Restored unsaved edits from {} (press Save to keep them)", autosave.saved_at));
+    }
+
+    /// Queue a message as if the automation thread had sent it (debug builds
+    /// only), so UI handling can be exercised without running automation
+    #[cfg(debug_assertions)]
+    fn inject_message(&self, msg: AutomationMessage) {
+        let _ = self.gui_sender.send(msg);
+    }
+
+    /// Buttons firing one sample of each `AutomationMessage` variant
+    #[cfg(debug_assertions)]
+    fn debug_message_controls(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("🐞 Debug messages")
+            .default_open(false)
+            .show(ui, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    if ui.button("Log").clicked() {
+                        self.inject_message(AutomationMessage::Log("Test log message".to_string()));
+                    }
+                    if ui.button("Status").clicked() {
+                        self.inject_message(AutomationMessage::Status("Test status".to_string()));
+                    }
+                    if ui.button("Timer").clicked() {
+                        self.inject_message(AutomationMessage::UpdateTimer(3725));
+                    }
+                    if ui.button("Error").clicked() {
+                        self.inject_message(AutomationMessage::ErrorPopup("Test error".to_string()));
+                    }
+                    if ui.button("Calibration").clicked() {
+                        self.inject_message(AutomationMessage::Calibration(CalibrationReport {
+                            steps: self.points.iter().map(|p| seccamcloud::StepTiming {
+                                name: p.name.clone(),
+                                measured_ms: Some(1200),
+                                suggested_delay_sec: 3,
+                                timed_out: false,
+                            }).collect(),
+                            suggested_step_delay: 3,
+                            suggested_step4_wait: 5,
+                        }));
+                    }
+                    if ui.button("Stop").clicked() {
+                        self.inject_message(AutomationMessage::Stop);
+                    }
+                });
+            });
+    }
+}
+
+// ============================================================================
+// STEP ACTION EDITOR
+// ============================================================================
+
+/// Action kind picker plus parameter field for one point in edit mode.
+/// "Classic" clears the action so the point follows the fixed choreography.
+fn step_action_editor(ui: &mut egui::Ui, index: usize, action: &mut Option<StepAction>) {
+    let current = action.as_ref().map_or("Classic".to_string(), |a| a.label());
+    egui::ComboBox::from_id_salt(("step_action", index))
+        .selected_text(current)
+        .width(110.0)
+        .show_ui(ui, |ui| {
+            if ui.selectable_label(action.is_none(), "Classic").clicked() {
+                *action = None;
+            }
+            if ui.selectable_label(matches!(action, Some(StepAction::Click)), "Click").clicked() {
+                *action = Some(StepAction::Click);
+            }
+            if ui.selectable_label(matches!(action, Some(StepAction::TypeText(_))), "Click + type text").clicked() {
+                *action = Some(StepAction::TypeText(DEFAULT_TEXT_TEMPLATE.to_string()));
+            }
+            if ui.selectable_label(matches!(action, Some(StepAction::TypeDate(_))), "Click + type date").clicked() {
+                *action = Some(StepAction::TypeDate(DEFAULT_DATE_FORMAT.to_string()));
+            }
+            if ui.selectable_label(matches!(action, Some(StepAction::Wait(_))), "Wait").clicked() {
+                *action = Some(StepAction::Wait(DEFAULT_STEP4_WAIT as u32));
+            }
+            if ui.selectable_label(matches!(action, Some(StepAction::LongWait)), "Long wait").clicked() {
+                *action = Some(StepAction::LongWait);
+            }
+        });
+
+    match action {
+        Some(StepAction::TypeText(text)) | Some(StepAction::TypeDate(text)) => {
+            ui.add(egui::TextEdit::singleline(text).desired_width(100.0));
+        }
+        Some(StepAction::Wait(secs)) => {
+            ui.add(egui::DragValue::new(secs).clamp_range(0..=3600).suffix("s"));
+        }
+        _ => {}
+    }
+}
+
+/// Mouse button and single/double choice for a point
+fn click_style_editor(ui: &mut egui::Ui, index: usize, point: &mut ClickPoint) {
+    const STYLES: [(ClickButton, ClickKind); 5] = [
+        (ClickButton::Left, ClickKind::Single),
+        (ClickButton::Left, ClickKind::Double),
+        (ClickButton::Right, ClickKind::Single),
+        (ClickButton::Right, ClickKind::Double),
+        (ClickButton::Middle, ClickKind::Single),
+    ];
+
+    egui::ComboBox::from_id_salt(("click_style", index))
+        .selected_text(point.click_label())
+        .width(90.0)
+        .show_ui(ui, |ui| {
+            for (button, kind) in STYLES {
+                let style = ClickPoint::new("", 0, 0).with_click(button, kind);
+                let selected = point.button == button && point.click_kind == kind;
+                if ui.selectable_label(selected, style.click_label()).clicked() {
+                    point.button = button;
+                    point.click_kind = kind;
+                }
+            }
+        });
+}
+
+/// Optional per-point delay; unchecked uses the global step delay
+fn delay_override_editor(ui: &mut egui::Ui, point: &mut ClickPoint, step_delay: i32) {
+    let mut custom = point.delay_override.is_some();
+    if ui.checkbox(&mut custom, "⏱")
+        .on_hover_text("Wait a custom time after this click instead of the step delay")
+        .changed()
+    {
+        point.delay_override = custom.then_some(step_delay);
+    }
+    if let Some(delay) = &mut point.delay_override {
+        ui.add(egui::DragValue::new(delay).clamp_range(0..=600).suffix("s"));
+    }
 }
 
 // ============================================================================
@@ -296,30 +1398,138 @@ impl AppState {
 
 struct AutomationApp {
     state: AppState,
+    shutdown_requested: Arc<AtomicBool>,
 }
 
 impl AutomationApp {
-    fn new(_cc: &eframe::CreationContext<'_>, args: CliArgs) -> Self {
+    fn new(cc: &eframe::CreationContext<'_>, args: CliArgs) -> Self {
+        // Ctrl+C / SIGTERM: request shutdown and wake the UI so it runs promptly
+        let shutdown_requested = Arc::new(AtomicBool::new(false));
+        let flag = shutdown_requested.clone();
+        let ctx = cc.egui_ctx.clone();
+        if let Err(e) = ctrlc::set_handler(move || {
+            flag.store(true, Ordering::SeqCst);
+            ctx.request_repaint();
+        }) {
+            eprintln!("Warning: Failed to install signal handler: {}", e);
+        }
+
+        let state = AppState::new(args);
+        state.hotkeys.wake_on_event(&cc.egui_ctx);
+
         Self {
-            state: AppState::new(args),
+            state,
+            shutdown_requested,
         }
     }
 }
 
 impl eframe::App for AutomationApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // Signal handler asked us to exit
+        if self.shutdown_requested.swap(false, Ordering::SeqCst) {
+            self.state.add_log("Shutdown signal received");
+            self.state.shutdown();
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            return;
+        }
+
         // Process messages
         self.state.process_messages();
+        self.state.process_video_messages(ctx);
+        if self.state.preview_recorder.is_some() {
+            ctx.request_repaint_after(Duration::from_secs_f64(1.0 / PREVIEW_FPS));
+        }
 
-        // Check emergency stop
-        if self.state.running && self.state.hotkeys.check_emergency_stop() {
-            self.state.add_log("EMERGENCY STOP TRIGGERED");
-            self.state.stop_automation();
+        self.state.autosave_if_due();
+        if self.state.edit_mode {
+            if let Some(interval) = self.state.autosave_interval {
+                ctx.request_repaint_after(interval);
+            }
         }
 
-        // Request repaint for timer updates
+        // Publish our window bounds (physical pixels) for the self-click guard
+        let bounds = ctx.input(|i| {
+            let viewport = i.viewport();
+            if viewport.minimized == Some(true) {
+                return None;
+            }
+            viewport.outer_rect.map(|rect| {
+                let ppp = i.pixels_per_point;
+                ScreenRect::new(
+                    (rect.min.x * ppp) as i32,
+                    (rect.min.y * ppp) as i32,
+                    (rect.width() * ppp).ceil() as i32,
+                    (rect.height() * ppp).ceil() as i32,
+                )
+            })
+        });
+        *self.state.window_bounds.lock().unwrap() = bounds;
+
+        // Global hotkeys
+        for action in self.state.hotkeys.poll() {
+            self.state.run_hotkey_action(action);
+        }
+
+        // Request repaint for timer updates and the stop key; idle waits for input
         if self.state.running {
-            ctx.request_repaint_after(Duration::from_millis(100));
+            ctx.request_repaint_after(self.state.refresh_interval);
+        }
+
+        // Invalid configuration dialog
+        if let Some(error) = self.state.config_error.clone() {
+            egui::Window::new("⚠ Configuration Error")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    ui.label(&error);
+                    ui.label("Using backup/default click points instead. Fix the file and restart.");
+                    ui.add_space(8.0);
+                    if ui.button("OK").clicked() {
+                        self.state.config_error = None;
+                    }
+                });
+        }
+
+        // Restore edits auto-saved by a previous session
+        if let Some(autosave) = self.state.autosave_offer.clone() {
+            egui::Window::new("💾 Unsaved Edits Found")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    ui.label(format!(
+                        "{} points and timing settings were auto-saved at {} but never saved.",
+                        autosave.points.len(),
+                        autosave.saved_at
+                    ));
+                    ui.label("Restore them?");
+                    ui.add_space(8.0);
+                    ui.horizontal(|ui| {
+                        if ui.button("Restore").clicked() {
+                            self.state.autosave_offer = None;
+                            self.state.restore_autosave(autosave);
+                        }
+                        if ui.button("Discard").clicked() {
+                            self.state.autosave_offer = None;
+                            discard_autosave();
+                            self.state.add_log("Discarded auto-saved edits");
+                        }
+                    });
+                });
+        }
+
+        if !self.state.previews.is_empty() {
+            egui::Window::new("📹 Preview")
+                .resizable(true)
+                .default_width(360.0)
+                .show(ctx, |ui| {
+                    for (camera, texture) in &self.state.previews {
+                        ui.label(camera);
+                        ui.add(egui::Image::new(texture).shrink_to_fit());
+                    }
+                });
         }
 
         // Top panel
@@ -381,23 +1591,59 @@ impl eframe::App for AutomationApp {
                                 if ui
                                     .add_enabled(
                                         !self.state.running,
-                                        egui::Button::new("▶ Start").min_size([140.0, 30.0].into()),
+                                        egui::Button::new("▶ Start").min_size([92.0, 30.0].into()),
                                     )
                                     .clicked()
                                 {
                                     self.state.start_automation();
                                 }
 
+                                let pause_label = if self.state.is_paused() { "▶ Resume" } else { "⏸ Pause" };
+                                if ui
+                                    .add_enabled(
+                                        self.state.running,
+                                        egui::Button::new(pause_label).min_size([92.0, 30.0].into()),
+                                    )
+                                    .on_hover_text("Hold the sequence without losing progress")
+                                    .clicked()
+                                {
+                                    self.state.toggle_pause();
+                                }
+
                                 if ui
                                     .add_enabled(
                                         self.state.running,
-                                        egui::Button::new("⏹ Stop").min_size([140.0, 30.0].into()),
+                                        egui::Button::new("⏹ Stop").min_size([92.0, 30.0].into()),
                                     )
                                     .clicked()
                                 {
                                     self.state.stop_automation();
                                 }
                             });
+
+                            ui.horizontal(|ui| {
+                                if ui
+                                    .add_enabled(
+                                        !self.state.running,
+                                        egui::Button::new("1️⃣ Run Once").min_size([140.0, 24.0].into()),
+                                    )
+                                    .on_hover_text("Run the full sequence a single time, then stop")
+                                    .clicked()
+                                {
+                                    self.state.run_once();
+                                }
+
+                                if ui
+                                    .add_enabled(
+                                        !self.state.running,
+                                        egui::Button::new("📏 Calibrate Timing").min_size([140.0, 24.0].into()),
+                                    )
+                                    .on_hover_text("Run the sequence once and measure how long each step takes")
+                                    .clicked()
+                                {
+                                    self.state.start_calibration();
+                                }
+                            });
                         });
                     });
 
@@ -428,6 +1674,29 @@ impl eframe::App for AutomationApp {
                             );
                         });
 
+                        ui.horizontal(|ui| {
+                            ui.label("Log Progress Every:");
+                            ui.add(
+                                egui::DragValue::new(&mut self.state.wait_milestone_min)
+                                    .clamp_range(0..=240)
+                                    .suffix(" m")
+                                    .speed(0.1),
+                            )
+                            .on_hover_text("Activity log entry during the long wait (0 = off)");
+                        });
+
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut self.state.limit_iterations, "Stop After:")
+                                .on_hover_text("Stop by itself after this many iterations (unchecked = run until stopped)");
+                            ui.add_enabled(
+                                self.state.limit_iterations,
+                                egui::DragValue::new(&mut self.state.max_iterations)
+                                    .clamp_range(1..=10_000)
+                                    .suffix(" iterations")
+                                    .speed(0.1),
+                            );
+                        });
+
                         ui.add_space(4.0);
 
                         ui.horizontal(|ui| {
@@ -459,6 +1728,121 @@ impl eframe::App for AutomationApp {
                             );
                         });
 
+                        ui.horizontal(|ui| {
+                            ui.label("Start Delay:");
+                            ui.add(
+                                egui::DragValue::new(&mut self.state.start_delay)
+                                    .clamp_range(0..=60)
+                                    .suffix(" s")
+                                    .speed(0.1),
+                            )
+                            .on_hover_text("Countdown before the first click");
+                        });
+
+                        ui.horizontal(|ui| {
+                            ui.label("Max Clicks/min:");
+                            ui.add(
+                                egui::DragValue::new(&mut self.state.max_clicks_per_minute)
+                                    .clamp_range(0..=600)
+                                    .speed(0.5),
+                            )
+                            .on_hover_text("0 = unlimited");
+                        });
+
+                        ui.horizontal(|ui| {
+                            ui.label("Repeat Guard:");
+                            ui.add(
+                                egui::DragValue::new(&mut self.state.replay_guard_ms)
+                                    .clamp_range(0..=10_000)
+                                    .suffix(" ms")
+                                    .speed(10.0),
+                            )
+                            .on_hover_text("Minimum time between two clicks at the same spot (0 = off)");
+                        });
+
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut self.state.focus_click, "Focus click")
+                                .on_hover_text("Click here at the start of each iteration so the target window has focus");
+                            ui.add_enabled(
+                                self.state.focus_click,
+                                egui::DragValue::new(&mut self.state.focus_point.x).prefix("x:"),
+                            );
+                            ui.add_enabled(
+                                self.state.focus_click,
+                                egui::DragValue::new(&mut self.state.focus_point.y).prefix("y:"),
+                            );
+                        });
+
+                        ui.horizontal(|ui| {
+                            ui.label("Step 2 Text:");
+                            ui.text_edit_singleline(&mut self.state.text_template)
+                                .on_hover_text("Placeholders: {date} {date:%d-%m-%Y} {time} {iteration} {env:NAME}");
+                        });
+
+                        ui.horizontal(|ui| {
+                            ui.label("Date Format:");
+                            ui.add(egui::TextEdit::singleline(&mut self.state.date_format).desired_width(90.0))
+                                .on_hover_text("strftime format for {date}, e.g. %d-%m-%Y, %m/%d/%Y or %Y-%m-%d");
+                            ui.label(
+                                egui::RichText::new(format_date(Local::now(), &self.state.date_format))
+                                    .weak(),
+                            );
+                        });
+
+                        ui.checkbox(&mut self.state.verify_text, "Verify typed text via clipboard");
+                        ui.checkbox(&mut self.state.humanize, "Humanize clicks")
+                            .on_hover_text("Offset clicks by a few pixels and vary step delays by ±20%");
+                        ui.checkbox(&mut self.state.self_click_guard, "Never click on this window")
+                            .on_hover_text("Pause if a click point falls inside the SecCamCloud window");
+
+                        if self.state.screenshots.is_enabled() {
+                            ui.checkbox(&mut self.state.capture_each_step, "Screenshot after each step");
+                            ui.horizontal(|ui| {
+                                ui.label("Screenshot Settle:");
+                                ui.add(
+                                    egui::DragValue::new(&mut self.state.screenshot_settle_ms)
+                                        .clamp_range(0..=5_000)
+                                        .suffix(" ms")
+                                        .speed(10.0),
+                                )
+                                .on_hover_text("Pause before step and failure screenshots so the screen has finished updating");
+                            });
+                        }
+
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut self.state.idle_cpu_gate, "Wait for idle CPU <");
+                            ui.add_enabled(
+                                self.state.idle_cpu_gate,
+                                egui::DragValue::new(&mut self.state.idle_cpu_threshold)
+                                    .clamp_range(1..=100)
+                                    .suffix(" %")
+                                    .speed(0.1),
+                            );
+                        });
+
+                        if self.state.idle_cpu_gate {
+                            ui.horizontal(|ui| {
+                                ui.label("Idle Timeout:");
+                                ui.add(
+                                    egui::DragValue::new(&mut self.state.idle_cpu_timeout)
+                                        .clamp_range(1..=300)
+                                        .suffix(" s")
+                                        .speed(0.1),
+                                );
+                            });
+                        }
+
+                        if let Some(report) = &self.state.calibration {
+                            let label = format!(
+                                "Apply calibration ({}s / {}s)",
+                                report.suggested_step_delay, report.suggested_step4_wait
+                            );
+                            if ui.button(label).clicked() {
+                                self.state.step_delay = report.suggested_step_delay.clamp(0, 60);
+                                self.state.step4_wait = report.suggested_step4_wait.clamp(0, 300);
+                            }
+                        }
+
                         ui.add_space(4.0);
                         ui.checkbox(&mut self.state.dry_run, "🧪 Dry Run");
                     });
@@ -470,11 +1854,16 @@ impl eframe::App for AutomationApp {
                         ui.horizontal(|ui| {
                             ui.heading("📍 Points");
                             ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                                ui.checkbox(&mut self.state.edit_mode, "✏ Edit");
+                                if ui.checkbox(&mut self.state.edit_mode, "✏ Edit").changed()
+                                    && !self.state.edit_mode
+                                {
+                                    self.state.discard_autosave_if_unchanged();
+                                }
                             });
                         });
                         ui.separator();
 
+                        let mut remove = None;
                         egui::ScrollArea::vertical().max_height(180.0).show(ui, |ui| {
                             for (i, point) in self.state.points.iter_mut().enumerate() {
                                 ui.horizontal(|ui| {
@@ -484,36 +1873,77 @@ impl eframe::App for AutomationApp {
                                         ui.text_edit_singleline(&mut point.name);
                                         ui.add(egui::DragValue::new(&mut point.x).prefix("x:"));
                                         ui.add(egui::DragValue::new(&mut point.y).prefix("y:"));
+                                        step_action_editor(ui, i, &mut point.action);
+                                        if point.action.as_ref().is_none_or(StepAction::clicks) {
+                                            click_style_editor(ui, i, point);
+                                            delay_override_editor(ui, point, self.state.step_delay);
+                                        }
+                                        if ui.small_button("🗑").on_hover_text("Remove point").clicked() {
+                                            remove = Some(i);
+                                        }
                                     } else {
                                         ui.label(&point.name);
                                         ui.label(
                                             egui::RichText::new(format!("({}, {})", point.x, point.y))
                                                 .weak(),
                                         );
+                                        if let Some(action) = &point.action {
+                                            ui.label(egui::RichText::new(action.label()).weak().italics());
+                                        }
+                                        if point.button != ClickButton::Left || point.click_kind != ClickKind::Single {
+                                            ui.label(egui::RichText::new(point.click_label()).weak().italics());
+                                        }
+                                        if let Some(delay) = point.delay_override {
+                                            ui.label(egui::RichText::new(format!("then {}s", delay)).weak().italics());
+                                        }
                                     }
                                 });
                             }
                         });
+                        if let Some(i) = remove {
+                            self.state.points.remove(i);
+                        }
 
                         if self.state.edit_mode {
                             ui.separator();
-                            if ui.button("💾 Save").clicked() {
-                                self.state.save_points();
-                            }
+                            ui.horizontal(|ui| {
+                                if ui.button("➕ Add").clicked() {
+                                    let n = self.state.points.len() + 1;
+                                    self.state.points.push(ClickPoint::new(format!("Point {}", n), 0, 0));
+                                }
+                                if ui.button("💾 Save").clicked() {
+                                    self.state.save_points();
+                                }
+                            });
+                            ui.label(
+                                egui::RichText::new("Without any actions the first 6 points run the classic sequence")
+                                    .weak()
+                                    .small(),
+                            );
                         }
                     });
 
                     ui.add_space(8.0);
 
+                    #[cfg(debug_assertions)]
+                    {
+                        self.state.debug_message_controls(ui);
+                        ui.add_space(8.0);
+                    }
+
                     // Info
                     ui.group(|ui| {
                         ui.label(egui::RichText::new("ℹ Info").strong());
                         ui.separator();
 
-                        if is_windows() {
-                            ui.label("🔴 DELETE = Emergency Stop");
-                        } else {
-                            ui.label("⚠ Hotkeys: Windows only");
+                        let mut stop_key = false;
+                        for (key, action) in self.state.hotkeys.active_keys() {
+                            stop_key |= action == HotkeyAction::EmergencyStop;
+                            let icon = if action == HotkeyAction::EmergencyStop { "🔴" } else { "⌨" };
+                            ui.label(format!("{} {} = {}", icon, key, action.label()));
+                        }
+                        if !stop_key {
+                            ui.label("⚠ Emergency stop key unavailable");
                         }
 
                         ui.label("📄 automation_log.txt");
@@ -525,6 +1955,14 @@ impl eframe::App for AutomationApp {
                         if self.state.screenshots.is_enabled() {
                             ui.label("📸 screenshots/");
                         }
+
+                        let features = active_features();
+                        ui.label(if features.is_empty() {
+                            "🧩 Features: none".to_string()
+                        } else {
+                            format!("🧩 Features: {}", features.join(", "))
+                        })
+                        .on_hover_text("Compile-time features (cargo --features screenshots,video,youtube)");
                     });
                 });
 
@@ -532,7 +1970,21 @@ impl eframe::App for AutomationApp {
 
                 // Right panel - Log
                 ui.vertical(|ui| {
-                    ui.heading("📋 Activity Log");
+                    ui.horizontal(|ui| {
+                        ui.heading("📋 Activity Log");
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            if ui
+                                .add_enabled(
+                                    !self.state.log_messages.is_empty(),
+                                    egui::Button::new("💾 Export"),
+                                )
+                                .on_hover_text("Save the visible log to a .txt or .json file")
+                                .clicked()
+                            {
+                                self.state.export_log();
+                            }
+                        });
+                    });
                     ui.separator();
 
                     egui::ScrollArea::vertical()
@@ -557,11 +2009,7 @@ impl eframe::App for AutomationApp {
     }
 
     fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
-        if self.state.running {
-            self.state.stop_automation();
-            thread::sleep(Duration::from_millis(500));
-        }
-        self.state.telemetry.log("Application exiting");
+        self.state.shutdown();
     }
 }
 
@@ -572,6 +2020,43 @@ impl eframe::App for AutomationApp {
 fn main() -> Result<(), eframe::Error> {
     let args = CliArgs::parse();
 
+    let transport = match args.rtsp_transport.as_deref() {
+        Some("tcp") => RtspTransport::Tcp,
+        Some("udp") => RtspTransport::Udp,
+        _ => RtspTransport::Default,
+    };
+    // SAFETY: still single-threaded; nothing has been spawned yet
+    unsafe { set_rtsp_transport(transport) };
+
+    if let Some(dir) = &args.data_dir {
+        set_data_dir(dir);
+    }
+
+    if args.print_config {
+        std::process::exit(print_config(&args));
+    }
+
+    if let Some(Command::RunSequence { file, step_delay, max_retries, wait }) = &args.command {
+        std::process::exit(run_sequence(file, args.dry_run, *step_delay, *max_retries, *wait));
+    }
+
+    // Only the GUI reads clickpoints.json
+    if args.strict_config && args.config_files.is_empty() {
+        if let Err(e) = try_load_points() {
+            eprintln!("{}", e);
+            eprintln!("Refusing to start with --strict-config");
+            std::process::exit(2);
+        }
+    }
+
+    if !display_available() {
+        eprintln!("No display server found (DISPLAY and WAYLAND_DISPLAY are unset), so the GUI cannot start.");
+        eprintln!("On a headless machine use the command-line mode instead, e.g.:");
+        eprintln!("  seccamcloud run-sequence --file points.json");
+        eprintln!("  seccamcloud --print-config");
+        std::process::exit(2);
+    }
+
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([1000.0, 650.0])
@@ -0,0 +1,76 @@
+// ============================================================================
+// SecCamCloud - Paths Module
+// Version: 1.0.0
+// Author: Michael Lauzon
+// Rust Edition: 2024
+// License: GPLv2
+// ============================================================================
+
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+/// Environment variable overriding the data directory
+pub const DATA_DIR_ENV: &str = "SECCAM_DATA_DIR";
+
+/// Environment variable overriding where recordings are written
+pub const RECORDINGS_DIR_ENV: &str = "SECCAM_RECORDINGS_DIR";
+
+/// Recordings folder inside the data directory
+const RECORDINGS_DIR: &str = "recordings";
+
+/// Application name used for confy's default (legacy) location
+pub const CONFY_APP_NAME: &str = "SecCamCloud";
+
+/// File name of the confy backup config inside the data directory
+const CONFY_FILE: &str = "seccamcloud.toml";
+
+static DATA_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+// ============================================================================
+// DATA DIRECTORY
+// ============================================================================
+
+/// Override the data directory (e.g. from `--data-dir`). Only the first call
+/// takes effect; returns false if the directory was already resolved.
+pub fn set_data_dir(dir: impl Into<PathBuf>) -> bool {
+    DATA_DIR.set(dir.into()).is_ok()
+}
+
+/// Directory holding all configuration and state files.
+///
+/// Resolved once: an explicit [`set_data_dir`] wins, then `SECCAM_DATA_DIR`,
+/// then the current working directory.
+pub fn data_dir() -> &'static PathBuf {
+    DATA_DIR.get_or_init(|| {
+        std::env::var_os(DATA_DIR_ENV)
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("."))
+    })
+}
+
+/// Path of a file inside the data directory
+pub fn data_file(name: &str) -> PathBuf {
+    data_dir().join(name)
+}
+
+/// Explicit path for confy's config, so it lives alongside the JSON files
+pub fn confy_path() -> PathBuf {
+    data_file(CONFY_FILE)
+}
+
+/// Default directory for video recordings: `SECCAM_RECORDINGS_DIR`, else
+/// `recordings/` in the data directory. Made absolute so recordings don't
+/// depend on the working directory of a later process.
+pub fn recordings_dir() -> PathBuf {
+    let dir = std::env::var_os(RECORDINGS_DIR_ENV)
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| data_file(RECORDINGS_DIR));
+
+    std::path::absolute(&dir).unwrap_or(dir)
+}
@@ -33,12 +33,182 @@
 // 
 // ============================================================================
 
-use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
 use chrono::Local;
 use log::{info, warn};
 
+#[cfg(feature = "screenshots")]
+use crate::util::sanitize_filename;
+
+// ============================================================================
+// SCREEN SAMPLE
+// ============================================================================
+
+/// Sampling step (in pixels) used when downsampling the screen
+const SAMPLE_STEP: u32 = 4;
+/// Per-pixel luminance delta that counts as "changed"
+const SAMPLE_PIXEL_TOLERANCE: u8 = 24;
+
+/// Frame rate of screen clips (grabs are slow, so keep this low)
+#[cfg(all(feature = "screenshots", feature = "video"))]
+const CLIP_FPS: f64 = 5.0;
+
+/// Downsampled grayscale copy of the screen, used to detect visual changes
+#[derive(Debug, Clone)]
+pub struct ScreenSample {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl ScreenSample {
+    #[cfg(feature = "screenshots")]
+    fn from_image(img: &image::RgbaImage) -> Self {
+        let width = img.width().div_ceil(SAMPLE_STEP);
+        let height = img.height().div_ceil(SAMPLE_STEP);
+        let mut pixels = Vec::with_capacity((width * height) as usize);
+
+        for y in (0..img.height()).step_by(SAMPLE_STEP as usize) {
+            for x in (0..img.width()).step_by(SAMPLE_STEP as usize) {
+                let p = img.get_pixel(x, y);
+                let luma = (p[0] as u32 * 299 + p[1] as u32 * 587 + p[2] as u32 * 114) / 1000;
+                pixels.push(luma as u8);
+            }
+        }
+
+        Self { width, height, pixels }
+    }
+
+    /// Fraction (0.0 - 1.0) of sampled pixels that differ from `other`.
+    /// Samples of different sizes are treated as completely different.
+    pub fn difference(&self, other: &ScreenSample) -> f64 {
+        if self.width != other.width || self.height != other.height || self.pixels.is_empty() {
+            return 1.0;
+        }
+
+        let changed = self.pixels.iter()
+            .zip(&other.pixels)
+            .filter(|(a, b)| a.abs_diff(**b) > SAMPLE_PIXEL_TOLERANCE)
+            .count();
+
+        changed as f64 / self.pixels.len() as f64
+    }
+}
+
+/// True if at least `ratio` of sampled pixels match the first pixel's color
+#[cfg(feature = "screenshots")]
+fn is_blank_image(img: &image::RgbaImage, ratio: f64) -> bool {
+    const STEP: usize = 16;
+    const TOLERANCE: u8 = 8;
+
+    let mut pixels = img.pixels().step_by(STEP);
+    let Some(first) = pixels.next() else {
+        return true;
+    };
+
+    let (mut total, mut same) = (1usize, 1usize);
+    for p in pixels {
+        total += 1;
+        if p.0.iter().zip(first.0.iter()).take(3).all(|(a, b)| a.abs_diff(*b) <= TOLERANCE) {
+            same += 1;
+        }
+    }
+
+    same as f64 / total as f64 >= ratio
+}
+
+// ============================================================================
+// SCREENSHOT CONFIGURATION
+// ============================================================================
+
+/// What a capture does when another capture is already in progress
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverlapPolicy {
+    /// Wait up to this many milliseconds for the running capture, then skip
+    Wait(u64),
+    /// Skip immediately
+    Skip,
+}
+
+#[derive(Debug, Clone)]
+pub struct ScreenshotConfig {
+    pub output_dir: String,
+    /// Frame reads attempted by the scrap backend before giving up
+    pub scrap_retries: u32,
+    /// Pause between scrap reads (compositors often return WouldBlock at first)
+    pub scrap_retry_delay_ms: u64,
+    /// Treat near-uniform (e.g. all-black) captures as a failed backend. Off by
+    /// default, since a dark or idle screen is a legitimate capture
+    pub reject_blank_frames: bool,
+    /// Fraction of sampled pixels sharing one color that makes a frame "blank"
+    pub blank_ratio: f64,
+    /// Only one capture runs at a time (some backends aren't reentrant)
+    pub overlap_policy: OverlapPolicy,
+}
+
+impl Default for ScreenshotConfig {
+    fn default() -> Self {
+        Self {
+            output_dir: "screenshots".to_string(),
+            scrap_retries: 5,
+            scrap_retry_delay_ms: 50,
+            reject_blank_frames: false,
+            blank_ratio: 0.995,
+            overlap_policy: OverlapPolicy::Wait(2000),
+        }
+    }
+}
+
+impl ScreenshotConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_output_dir(mut self, dir: impl Into<String>) -> Self {
+        self.output_dir = dir.into();
+        self
+    }
+
+    pub fn with_scrap_retries(mut self, retries: u32, delay_ms: u64) -> Self {
+        self.scrap_retries = retries.max(1);
+        self.scrap_retry_delay_ms = delay_ms;
+        self
+    }
+
+    pub fn with_blank_frame_check(mut self, enabled: bool, ratio: f64) -> Self {
+        self.reject_blank_frames = enabled;
+        self.blank_ratio = ratio.clamp(0.5, 1.0);
+        self
+    }
+
+    pub fn with_overlap_policy(mut self, policy: OverlapPolicy) -> Self {
+        self.overlap_policy = policy;
+        self
+    }
+}
+
+// ============================================================================
+// DISPLAYS
+// ============================================================================
+
+/// A monitor as reported by the screenshots crate, for display pickers
+#[derive(Debug, Clone, PartialEq)]
+pub struct DisplayInfo {
+    /// Position in `Screen::all()` order
+    pub index: usize,
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    /// Top-left corner in the virtual desktop
+    pub x: i32,
+    pub y: i32,
+    pub scale_factor: f32,
+    pub is_primary: bool,
+}
+
 // ============================================================================
 // SCREENSHOT MANAGER
 // ============================================================================
@@ -46,11 +216,19 @@ use log::{info, warn};
 pub struct ScreenshotManager {
     enabled: bool,
     output_dir: String,
+    config: ScreenshotConfig,
+    /// Held for the duration of each screen grab
+    #[cfg_attr(not(feature = "screenshots"), allow(dead_code))]
+    capture_lock: Mutex<()>,
 }
 
 impl ScreenshotManager {
     pub fn new(enabled: bool) -> Arc<Self> {
-        let output_dir = "screenshots".to_string();
+        Self::with_config(enabled, ScreenshotConfig::default())
+    }
+
+    pub fn with_config(enabled: bool, config: ScreenshotConfig) -> Arc<Self> {
+        let output_dir = config.output_dir.clone();
 
         if enabled {
             let _ = std::fs::create_dir_all(&output_dir);
@@ -59,6 +237,8 @@ impl ScreenshotManager {
         Arc::new(Self {
             enabled,
             output_dir,
+            config,
+            capture_lock: Mutex::new(()),
         })
     }
 
@@ -71,35 +251,232 @@ impl ScreenshotManager {
         #[cfg(feature = "screenshots")]
         {
             let timestamp = Local::now().format("%Y%m%d_%H%M%S");
-            let filename = format!("{}/{}_{}_{}.png", self.output_dir, step_name, suffix, timestamp);
+            let filename = format!(
+                "{}/{}_{}_{}.png",
+                self.output_dir, sanitize_filename(step_name), sanitize_filename(suffix), timestamp
+            );
+
+            if let Some((img, backend)) = self.grab() {
+                if img.save(&filename).is_ok() {
+                    info!("Screenshot captured with {}: {}", backend, filename);
+                    return Some(filename);
+                }
+                warn!("Failed to save screenshot from {}", backend);
+                return None;
+            }
+
+            warn!("Failed to capture screenshot with all methods");
+        }
+
+        None
+    }
+
+    /// Record `seconds` of the screen to `{dir}/{step}_{suffix}_{timestamp}.mp4`.
+    /// Blocks for the duration of the clip, or until `stop_flag` is set (the
+    /// frames so far are kept).
+    #[cfg(all(feature = "screenshots", feature = "video"))]
+    pub fn record_clip(&self, step_name: &str, suffix: &str, seconds: u32, stop_flag: &AtomicBool) -> Option<String> {
+        use std::sync::atomic::Ordering;
+        use std::time::Instant;
+        use opencv::core::{Mat, Scalar, Size, CV_8UC3};
+        use opencv::prelude::*;
+        use opencv::videoio::VideoWriter;
+        use crate::vidrec::VideoFormat;
 
-            // Try captrs first (best for Linux, supports both X11 and Wayland)
-            if let Some(path) = self.capture_with_captrs(&filename) {
-                info!("Screenshot captured with captrs: {}", path);
-                return Some(path);
+        if !self.enabled || seconds == 0 || stop_flag.load(Ordering::SeqCst) {
+            return None;
+        }
+
+        let timestamp = Local::now().format("%Y%m%d_%H%M%S");
+        let filename = format!(
+            "{}/{}_{}_{}.mp4",
+            self.output_dir, sanitize_filename(step_name), sanitize_filename(suffix), timestamp
+        );
+        let duration = Duration::from_secs(seconds as u64);
+        let start = Instant::now();
+
+        let mut writer: Option<(VideoWriter, u32, u32)> = None;
+        let mut written = 0u64;
+
+        while start.elapsed() < duration {
+            if stop_flag.load(Ordering::SeqCst) {
+                info!("Screen clip stopped early: {}", filename);
+                break;
             }
+            let Some((img, _)) = self.grab() else {
+                thread::sleep(Duration::from_millis((1000.0 / CLIP_FPS) as u64));
+                continue;
+            };
+            let (width, height) = img.dimensions();
 
-            // Try screenshots crate second (best for macOS and Windows)
-            if let Some(path) = self.capture_with_screenshots(&filename) {
-                info!("Screenshot captured with screenshots crate: {}", path);
-                return Some(path);
+            if writer.is_none() {
+                let size = Size::new(width as i32, height as i32);
+                match VideoWriter::new(&filename, VideoFormat::MP4.fourcc(), CLIP_FPS, size, true) {
+                    Ok(w) if w.is_opened().unwrap_or(false) => writer = Some((w, width, height)),
+                    _ => {
+                        warn!("Failed to open clip writer: {}", filename);
+                        return None;
+                    }
+                }
             }
+            let Some((writer, w, h)) = writer.as_mut() else {
+                return None;
+            };
 
-            // Fallback to scrap (X11 compatibility)
-            if let Some(path) = self.capture_with_scrap(&filename) {
-                info!("Screenshot captured with scrap: {}", path);
-                return Some(path);
+            // Backends can disagree on resolution; the writer needs a fixed size
+            if (width, height) != (*w, *h) {
+                continue;
             }
 
-            warn!("Failed to capture screenshot with all methods");
+            let mut frame = Mat::new_rows_cols_with_default(
+                height as i32, width as i32, CV_8UC3, Scalar::all(0.0),
+            ).ok()?;
+            let bytes = frame.data_bytes_mut().ok()?;
+            for (dst, src) in bytes.chunks_exact_mut(3).zip(img.pixels()) {
+                dst[0] = src[2];
+                dst[1] = src[1];
+                dst[2] = src[0];
+            }
+
+            // Repeat slow grabs so the clip plays back in real time
+            let due = (start.elapsed().as_secs_f64() * CLIP_FPS) as u64 + 1;
+            while written < due {
+                if writer.write(&frame).is_err() {
+                    warn!("Failed to write clip frame: {}", filename);
+                    return None;
+                }
+                written += 1;
+            }
+        }
+
+        let (mut writer, _, _) = writer?;
+        let _ = writer.release();
+        info!("Screen clip recorded ({} frames): {}", written, filename);
+        Some(filename)
+    }
+
+    /// Stub when the screenshots or video feature is disabled
+    #[cfg(not(all(feature = "screenshots", feature = "video")))]
+    pub fn record_clip(&self, _step_name: &str, _suffix: &str, _seconds: u32, _stop_flag: &AtomicBool) -> Option<String> {
+        None
+    }
+
+    /// Monitors available to the screenshots crate backend (empty if none or
+    /// the backend fails)
+    #[cfg(feature = "screenshots")]
+    pub fn list_displays() -> Vec<DisplayInfo> {
+        use screenshots::Screen;
+
+        match Screen::all() {
+            Ok(screens) => screens
+                .iter()
+                .enumerate()
+                .map(|(index, screen)| {
+                    let info = &screen.display_info;
+                    DisplayInfo {
+                        index,
+                        name: format!(
+                            "Display {}{}",
+                            index + 1,
+                            if info.is_primary { " (primary)" } else { "" }
+                        ),
+                        width: info.width,
+                        height: info.height,
+                        x: info.x,
+                        y: info.y,
+                        scale_factor: info.scale_factor,
+                        is_primary: info.is_primary,
+                    }
+                })
+                .collect(),
+            Err(e) => {
+                warn!("screenshots crate Screen::all failed: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Stub when the screenshots feature is disabled
+    #[cfg(not(feature = "screenshots"))]
+    pub fn list_displays() -> Vec<DisplayInfo> {
+        Vec::new()
+    }
+
+    /// Take a downsampled grayscale sample of the screen for change detection.
+    /// Works regardless of `enabled`.
+    #[cfg(feature = "screenshots")]
+    pub fn sample(&self) -> Option<ScreenSample> {
+        self.grab().map(|(img, _)| ScreenSample::from_image(&img))
+    }
+
+    /// Stub when the screenshots feature is disabled
+    #[cfg(not(feature = "screenshots"))]
+    pub fn sample(&self) -> Option<ScreenSample> {
+        None
+    }
+
+    /// Acquire the capture lock according to the overlap policy
+    #[cfg(feature = "screenshots")]
+    fn lock_capture(&self) -> Option<std::sync::MutexGuard<'_, ()>> {
+        let wait_ms = match self.config.overlap_policy {
+            OverlapPolicy::Skip => 0,
+            OverlapPolicy::Wait(ms) => ms,
+        };
+        let deadline = std::time::Instant::now() + Duration::from_millis(wait_ms);
+
+        loop {
+            match self.capture_lock.try_lock() {
+                Ok(guard) => return Some(guard),
+                // A panicked capture doesn't leave the backend in a bad state
+                Err(std::sync::TryLockError::Poisoned(e)) => return Some(e.into_inner()),
+                Err(std::sync::TryLockError::WouldBlock) => {}
+            }
+
+            if std::time::Instant::now() >= deadline {
+                warn!("Another capture is in progress, skipping this one");
+                return None;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    /// Grab the screen into memory, trying each backend in turn
+    #[cfg(feature = "screenshots")]
+    fn grab(&self) -> Option<(image::RgbaImage, &'static str)> {
+        let _guard = self.lock_capture()?;
+
+        // A backend that "works" but returns a blank image counts as failed
+        let accept = |img: image::RgbaImage, backend: &'static str| {
+            if self.config.reject_blank_frames && is_blank_image(&img, self.config.blank_ratio) {
+                warn!("{} produced a blank frame, trying next backend", backend);
+                None
+            } else {
+                Some((img, backend))
+            }
+        };
+
+        // Try captrs first (best for Linux, supports both X11 and Wayland)
+        if let Some(found) = self.grab_with_captrs().and_then(|img| accept(img, "captrs")) {
+            return Some(found);
+        }
+
+        // Try screenshots crate second (best for macOS and Windows)
+        if let Some(found) = self.grab_with_screenshots().and_then(|img| accept(img, "screenshots crate")) {
+            return Some(found);
+        }
+
+        // Fallback to scrap (X11 compatibility)
+        match self.grab_with_scrap() {
+            Ok(img) => return accept(img, "scrap"),
+            Err(e) => warn!("scrap capture failed: {}", e),
         }
 
         None
     }
 
-    /// Capture screenshot using captrs (X11 and Wayland)
+    /// Grab screen using captrs (X11 and Wayland)
     #[cfg(feature = "screenshots")]
-    fn capture_with_captrs(&self, filename: &str) -> Option<String> {
+    fn grab_with_captrs(&self) -> Option<image::RgbaImage> {
         use captrs::Capturer;
 
         match Capturer::new(0) {
@@ -118,12 +495,7 @@ impl ScreenshotManager {
                             rgba_data.push(pixel[3]); // A
                         }
 
-                        // Save as PNG
-                        if let Ok(img) = image::RgbaImage::from_raw(width as u32, height as u32, rgba_data) {
-                            if img.save(filename).is_ok() {
-                                return Some(filename.to_string());
-                            }
-                        }
+                        return image::RgbaImage::from_raw(width as u32, height as u32, rgba_data);
                     }
                     Err(e) => {
                         warn!("captrs capture_frame failed: {}", e);
@@ -138,9 +510,9 @@ impl ScreenshotManager {
         None
     }
 
-    /// Capture screenshot using screenshots crate (cross-platform, best for macOS/Windows)
+    /// Grab screen using screenshots crate (cross-platform, best for macOS/Windows)
     #[cfg(feature = "screenshots")]
-    fn capture_with_screenshots(&self, filename: &str) -> Option<String> {
+    fn grab_with_screenshots(&self) -> Option<image::RgbaImage> {
         use screenshots::Screen;
         
         // Get all screens
@@ -155,61 +527,56 @@ impl ScreenshotManager {
         // Get primary screen (first one)
         let screen = screens.first()?;
         
-        // Capture the screen
+        // Capture the screen (returns an image::RgbaImage)
         match screen.capture() {
-            Ok(image) => {
-                // The screenshots crate returns an image::RgbaImage
-                if image.save(filename).is_ok() {
-                    info!("Screenshot saved via screenshots crate: {}", filename);
-                    return Some(filename.to_string());
-                } else {
-                    warn!("Failed to save screenshot from screenshots crate");
-                }
-            }
+            Ok(image) => Some(image),
             Err(e) => {
                 warn!("screenshots crate capture failed: {}", e);
+                None
             }
         }
-        
-        None
     }
 
-    /// Capture screenshot using scrap (X11 only, fallback)
+    /// Grab screen using scrap (X11 only, fallback)
     #[cfg(feature = "screenshots")]
-    fn capture_with_scrap(&self, filename: &str) -> Option<String> {
+    fn grab_with_scrap(&self) -> Result<image::RgbaImage, String> {
         use image::{Rgba, RgbaImage};
         use scrap::{Capturer, Display};
+        use std::io::ErrorKind;
+
+        let display = Display::primary().map_err(|e| format!("no primary display: {}", e))?;
+        let mut capturer = Capturer::new(display).map_err(|e| format!("capturer init failed: {}", e))?;
+        let attempts = self.config.scrap_retries;
+        let (width, height) = (capturer.width(), capturer.height());
 
-        let display = Display::primary().ok()?;
-        let mut capturer = Capturer::new(display).ok()?;
-
-        for _ in 0..5 {
-            if let Ok(frame) = capturer.frame() {
-                let width = capturer.width();
-                let height = capturer.height();
-                let mut img = RgbaImage::new(width as u32, height as u32);
-
-                for y in 0..height {
-                    for x in 0..width {
-                        let idx = (y * width + x) * 4;
-                        if idx + 3 < frame.len() {
-                            img.put_pixel(
-                                x as u32,
-                                y as u32,
-                                Rgba([frame[idx + 2], frame[idx + 1], frame[idx], 255]),
-                            );
+        for _ in 0..attempts {
+            match capturer.frame() {
+                Ok(frame) => {
+                    let mut img = RgbaImage::new(width as u32, height as u32);
+
+                    for y in 0..height {
+                        for x in 0..width {
+                            let idx = (y * width + x) * 4;
+                            if idx + 3 < frame.len() {
+                                img.put_pixel(
+                                    x as u32,
+                                    y as u32,
+                                    Rgba([frame[idx + 2], frame[idx + 1], frame[idx], 255]),
+                                );
+                            }
                         }
                     }
-                }
 
-                if img.save(filename).is_ok() {
-                    return Some(filename.to_string());
+                    return Ok(img);
                 }
+                // Frame not ready yet - wait and retry
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                Err(e) => return Err(format!("frame read failed: {}", e)),
             }
-            thread::sleep(Duration::from_millis(50));
+            thread::sleep(Duration::from_millis(self.config.scrap_retry_delay_ms));
         }
 
-        None
+        Err(format!("frame not ready after {} attempts", attempts))
     }
     
     pub fn is_enabled(&self) -> bool {
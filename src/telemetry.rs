@@ -6,45 +6,377 @@
 // License: GPLv2
 // ============================================================================
 
-use std::fs::OpenOptions;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
 use std::io::Write;
-use std::sync::Arc;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 use chrono::Local;
 
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+pub const TELEMETRY_DIR: &str = "logs";
+pub const TELEMETRY_LOG: &str = "logs/telemetry.log";
+pub const TELEMETRY_CSV: &str = "logs/telemetry.csv";
+pub const TELEMETRY_JSONL: &str = "logs/telemetry.jsonl";
+
+/// Category used for free-form events logged with [`Telemetry::log`]
+const DEFAULT_CATEGORY: &str = "note";
+
+/// Counters always present in the session summary, even when zero
+const SUMMARY_COUNTERS: [&str; 3] = ["clicks", "errors", "iterations"];
+
+// ============================================================================
+// EVENTS AND SINKS
+// ============================================================================
+
+/// A single telemetry event as handed to each sink
+#[derive(Debug, Clone)]
+pub struct TelemetryEvent<'a> {
+    pub timestamp: String,
+    pub category: &'a str,
+    pub fields: &'a [(&'a str, String)],
+    /// Line for logs/telemetry.log; `category: key=value; ...` when None
+    pub text: Option<&'a str>,
+}
+
+impl TelemetryEvent<'_> {
+    /// Fields flattened to `key=value` pairs separated by `; `
+    pub fn flat_fields(&self) -> String {
+        self.fields
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+/// Destination for telemetry events
+pub trait TelemetrySink: Send + Sync {
+    fn write(&self, event: &TelemetryEvent);
+}
+
+/// Human-readable lines appended to logs/telemetry.log
+pub struct FileSink;
+
+impl TelemetrySink for FileSink {
+    fn write(&self, event: &TelemetryEvent) {
+        let line = match (event.text, event.fields) {
+            (Some(text), _) => format!("[{}] {}\n", event.timestamp, text),
+            (None, []) => format!("[{}] {}\n", event.timestamp, event.category),
+            (None, _) => format!("[{}] {}: {}\n", event.timestamp, event.category, event.flat_fields()),
+        };
+
+        if let Ok(mut file) = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(TELEMETRY_LOG)
+        {
+            let _ = file.write_all(line.as_bytes());
+        }
+    }
+}
+
+/// One JSON object per line in logs/telemetry.jsonl, e.g.
+/// `{"ts":"...","event":"START","hours":"11"}`. Field values are always
+/// strings, exactly as recorded; notes from [`Telemetry::log`] appear as
+/// `{"event":"note","message":"..."}`.
+pub struct JsonSink;
+
+impl JsonSink {
+    /// The JSON line (with trailing newline) written for `event`
+    fn line(event: &TelemetryEvent) -> String {
+        let mut object = serde_json::Map::new();
+        object.insert("ts".to_string(), event.timestamp.clone().into());
+        object.insert("event".to_string(), event.category.into());
+        for (key, value) in event.fields {
+            object.insert(key.to_string(), value.as_str().into());
+        }
+
+        let mut line = serde_json::Value::Object(object).to_string();
+        line.push('\n');
+        line
+    }
+}
+
+impl TelemetrySink for JsonSink {
+    fn write(&self, event: &TelemetryEvent) {
+        let line = Self::line(event);
+        if let Ok(mut file) = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(TELEMETRY_JSONL)
+        {
+            let _ = file.write_all(line.as_bytes());
+        }
+    }
+}
+
+/// One CSV row per event (timestamp, category, fields) for spreadsheet analysis
+pub struct CsvSink<W: Write + Send = File> {
+    writer: Mutex<csv::Writer<W>>,
+}
+
+impl CsvSink {
+    /// Append to `path`, writing the header row if the file is new or empty
+    pub fn new(path: &str) -> Result<Self, String> {
+        if let Some(parent) = Path::new(path).parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("Failed to open {}: {}", path, e))?;
+        let is_empty = file.metadata().map(|m| m.len() == 0).unwrap_or(true);
+        Self::from_writer(file, is_empty)
+    }
+}
+
+impl<W: Write + Send> CsvSink<W> {
+    /// Write rows to `writer`, starting with the header row if `write_header`
+    pub fn from_writer(writer: W, write_header: bool) -> Result<Self, String> {
+        let mut writer = csv::WriterBuilder::new().has_headers(false).from_writer(writer);
+        if write_header {
+            writer
+                .write_record(["timestamp", "category", "fields"])
+                .map_err(|e| format!("Failed to write CSV header: {}", e))?;
+            writer.flush().map_err(|e| format!("Failed to write CSV header: {}", e))?;
+        }
+
+        Ok(Self {
+            writer: Mutex::new(writer),
+        })
+    }
+}
+
+impl<W: Write + Send> TelemetrySink for CsvSink<W> {
+    fn write(&self, event: &TelemetryEvent) {
+        let mut writer = self.writer.lock().unwrap();
+        let _ = writer.write_record([event.timestamp.as_str(), event.category, &event.flat_fields()]);
+        let _ = writer.flush();
+    }
+}
+
 // ============================================================================
 // TELEMETRY SYSTEM
 // ============================================================================
 
 pub struct Telemetry {
     enabled: bool,
+    sinks: Vec<Box<dyn TelemetrySink>>,
+    counters: Arc<Mutex<HashMap<String, u64>>>,
+    started: Instant,
 }
 
 impl Telemetry {
+    /// Telemetry written to logs/telemetry.log and logs/telemetry.jsonl
     pub fn new(enabled: bool) -> Arc<Self> {
-        let telemetry = Arc::new(Self { enabled });
+        Self::with_sinks(enabled, vec![Box::new(FileSink), Box::new(JsonSink)])
+    }
+
+    /// Telemetry written to each of `sinks`
+    pub fn with_sinks(enabled: bool, sinks: Vec<Box<dyn TelemetrySink>>) -> Arc<Self> {
+        if enabled {
+            let _ = std::fs::create_dir_all(TELEMETRY_DIR);
+        }
+
+        let telemetry = Arc::new(Self {
+            enabled,
+            sinks,
+            counters: Arc::new(Mutex::new(HashMap::new())),
+            started: Instant::now(),
+        });
 
         if enabled {
-            let _ = std::fs::create_dir_all("logs");
             telemetry.log("Telemetry initialized");
         }
 
         telemetry
     }
 
+    /// Free-text note for humans
     pub fn log(&self, event: impl AsRef<str>) {
+        let message = event.as_ref();
+        self.emit(DEFAULT_CATEGORY, &[("message", message.to_string())], Some(message));
+    }
+
+    /// Named event with key/value fields, for machine analysis
+    /// (`event("START", &[("hours", "11")])`)
+    pub fn event(&self, name: &str, fields: &[(&str, &str)]) {
+        self.event_with_text(name, fields, None);
+    }
+
+    /// Like [`Telemetry::event`], but written to telemetry.log as `text`
+    /// instead of the `name: key=value` form
+    pub fn event_with_text(&self, name: &str, fields: &[(&str, &str)], text: Option<&str>) {
+        if !self.enabled {
+            return;
+        }
+        let fields: Vec<(&str, String)> = fields.iter().map(|(k, v)| (*k, v.to_string())).collect();
+        self.emit(name, &fields, text);
+    }
+
+    /// Add one to the session counter `key` (e.g. "clicks", "errors")
+    pub fn increment(&self, key: &str) {
         if !self.enabled {
             return;
         }
+        *self.counters.lock().unwrap().entry(key.to_string()).or_insert(0) += 1;
+    }
 
-        let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S%.3f");
-        let line = format!("[{}] {}\n", timestamp, event.as_ref());
+    /// Current value of a session counter
+    pub fn counter(&self, key: &str) -> u64 {
+        self.counters.lock().unwrap().get(key).copied().unwrap_or(0)
+    }
 
-        if let Ok(mut file) = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open("logs/telemetry.log")
+    /// Write a SUMMARY event with the session duration and every counter
+    /// (clicks, errors and iterations are always included). Called at exit and
+    /// by the emergency stop, so a session may end with more than one.
+    pub fn flush_summary(&self) {
+        if !self.enabled {
+            return;
+        }
+
+        let mut fields = vec![(
+            "duration_sec".to_string(),
+            format!("{:.1}", self.started.elapsed().as_secs_f64()),
+        )];
         {
-            let _ = file.write_all(line.as_bytes());
+            let counters = self.counters.lock().unwrap();
+            for key in SUMMARY_COUNTERS {
+                fields.push((key.to_string(), counters.get(key).copied().unwrap_or(0).to_string()));
+            }
+            let mut others: Vec<_> = counters
+                .iter()
+                .filter(|(key, _)| !SUMMARY_COUNTERS.contains(&key.as_str()))
+                .collect();
+            others.sort();
+            fields.extend(others.into_iter().map(|(key, value)| (key.clone(), value.to_string())));
         }
+
+        let fields: Vec<(&str, String)> = fields.iter().map(|(k, v)| (k.as_str(), v.clone())).collect();
+        self.record("SUMMARY", &fields);
+    }
+
+    /// Record a categorized event with key/value fields
+    pub fn record(&self, category: &str, fields: &[(&str, String)]) {
+        self.emit(category, fields, None);
+    }
+
+    fn emit(&self, category: &str, fields: &[(&str, String)], text: Option<&str>) {
+        if !self.enabled {
+            return;
+        }
+
+        let event = TelemetryEvent {
+            timestamp: Local::now().format("%Y-%m-%d %H:%M:%S%.3f").to_string(),
+            category,
+            fields,
+            text,
+        };
+
+        for sink in &self.sinks {
+            sink.write(&event);
+        }
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Recorded = Arc<Mutex<Vec<(String, Vec<(String, String)>)>>>;
+
+    /// Keeps every event's category and fields
+    struct MemorySink(Recorded);
+
+    impl TelemetrySink for MemorySink {
+        fn write(&self, event: &TelemetryEvent) {
+            let fields = event.fields.iter().map(|(k, v)| (k.to_string(), v.clone())).collect();
+            self.0.lock().unwrap().push((event.category.to_string(), fields));
+        }
+    }
+
+    fn memory_telemetry(enabled: bool) -> (Arc<Telemetry>, Recorded) {
+        let recorded = Recorded::default();
+        let telemetry = Telemetry::with_sinks(enabled, vec![Box::new(MemorySink(recorded.clone()))]);
+        (telemetry, recorded)
+    }
+
+    fn event<'a>(category: &'a str, fields: &'a [(&'a str, String)]) -> TelemetryEvent<'a> {
+        TelemetryEvent { timestamp: "2024-03-05 12:00:00.000".to_string(), category, fields, text: None }
+    }
+
+    #[test]
+    fn summary_reports_counters() {
+        let (telemetry, recorded) = memory_telemetry(true);
+        telemetry.increment("clicks");
+        telemetry.increment("clicks");
+        telemetry.increment("retries");
+        telemetry.flush_summary();
+
+        assert_eq!(telemetry.counter("clicks"), 2);
+        assert_eq!(telemetry.counter("errors"), 0);
+
+        let recorded = recorded.lock().unwrap();
+        let (category, fields) = recorded.last().unwrap();
+        assert_eq!(category, "SUMMARY");
+        let keys: Vec<&str> = fields.iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(keys, ["duration_sec", "clicks", "errors", "iterations", "retries"]);
+        let values: Vec<&str> = fields[1..].iter().map(|(_, v)| v.as_str()).collect();
+        assert_eq!(values, ["2", "0", "0", "1"]);
+    }
+
+    #[test]
+    fn disabled_telemetry_records_nothing() {
+        let (telemetry, recorded) = memory_telemetry(false);
+        telemetry.increment("clicks");
+        telemetry.log("note");
+        telemetry.flush_summary();
+
+        assert_eq!(telemetry.counter("clicks"), 0);
+        assert!(recorded.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn csv_header_written_once() {
+        let fields = [("hours", "11".to_string()), ("minutes", "30".to_string())];
+
+        let sink = CsvSink::from_writer(Vec::new(), true).unwrap();
+        sink.write(&event("START", &fields));
+        let first = sink.writer.into_inner().unwrap().into_inner().unwrap();
+
+        // Reopening a non-empty file appends rows only
+        let sink = CsvSink::from_writer(first, false).unwrap();
+        sink.write(&event("STOP", &[]));
+        let csv = String::from_utf8(sink.writer.into_inner().unwrap().into_inner().unwrap()).unwrap();
+
+        assert_eq!(
+            csv,
+            "timestamp,category,fields\n\
+             2024-03-05 12:00:00.000,START,hours=11; minutes=30\n\
+             2024-03-05 12:00:00.000,STOP,\n"
+        );
+    }
+
+    #[test]
+    fn json_line_has_string_fields() {
+        let fields = [("hours", "11".to_string())];
+        let line = JsonSink::line(&event("START", &fields));
+
+        assert!(line.ends_with('\n'));
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["event"], "START");
+        assert_eq!(value["ts"], "2024-03-05 12:00:00.000");
+        assert_eq!(value["hours"], "11");
     }
 }
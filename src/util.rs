@@ -0,0 +1,212 @@
+// ============================================================================
+// SecCamCloud - Utility Module
+// Version: 1.0.0
+// Author: Michael Lauzon
+// Rust Edition: 2024
+// License: GPLv2
+// ============================================================================
+
+use std::path::Path;
+
+use sysinfo::Disks;
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+/// Longest sanitized name (in characters), leaving room for timestamps/extensions
+const MAX_FILENAME_CHARS: usize = 100;
+
+/// Device names Windows refuses as file names (with any extension)
+const WINDOWS_RESERVED: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL",
+    "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9",
+    "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+// ============================================================================
+// FILENAMES
+// ============================================================================
+
+/// Make `name` safe to use as part of a file name on Windows, macOS and Linux.
+///
+/// Spaces and characters that are illegal on any of them (`<>:"/\|?*` and
+/// control characters) become `_`, trailing dots/spaces are trimmed, Windows
+/// device names are prefixed with `_`, and the result is capped at 100
+/// characters. Never returns an empty string.
+pub fn sanitize_filename(name: &str) -> String {
+    let mut safe: String = name
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' | ' ' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .take(MAX_FILENAME_CHARS)
+        .collect();
+
+    // Windows silently drops trailing dots and spaces
+    while safe.ends_with('.') {
+        safe.pop();
+    }
+
+    if safe.is_empty() {
+        return "unnamed".to_string();
+    }
+
+    let stem = safe.split('.').next().unwrap_or_default();
+    if WINDOWS_RESERVED.iter().any(|r| r.eq_ignore_ascii_case(stem)) {
+        safe.insert(0, '_');
+        // The prefix must not push the name past the cap
+        if safe.chars().count() > MAX_FILENAME_CHARS {
+            safe.pop();
+            while safe.ends_with('.') {
+                safe.pop();
+            }
+        }
+    }
+
+    safe
+}
+
+// ============================================================================
+// DISK SPACE
+// ============================================================================
+
+/// Free bytes on the volume holding `path` (the disk with the longest
+/// matching mount point), or None if it can't be determined
+pub fn available_space(path: &Path) -> Option<u64> {
+    let path = std::path::absolute(path).ok()?;
+    let path = path.canonicalize().unwrap_or(path);
+    let disks = Disks::new_with_refreshed_list();
+
+    disks
+        .list()
+        .iter()
+        .filter(|d| path.starts_with(d.mount_point()))
+        .max_by_key(|d| d.mount_point().as_os_str().len())
+        .map(|d| d.available_space())
+}
+
+// ============================================================================
+// RANDOM NUMBERS
+// ============================================================================
+
+/// Small seedable PRNG (SplitMix64) for jitter; not for anything security related.
+/// The same seed always yields the same sequence.
+#[derive(Debug, Clone)]
+pub struct SimpleRng {
+    state: u64,
+}
+
+impl SimpleRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Seeded from the clock, for when reproducibility doesn't matter
+    pub fn from_clock() -> Self {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
+        Self::new(nanos)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `-max..=max`
+    pub fn jitter(&mut self, max: i32) -> i32 {
+        if max <= 0 {
+            return 0;
+        }
+        (self.next_u64() % (2 * max as u64 + 1)) as i32 - max
+    }
+
+    /// Uniform in `[-1.0, 1.0)`
+    pub fn unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 52) as f64 - 1.0
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_sequence() {
+        let mut a = SimpleRng::new(42);
+        let mut b = SimpleRng::new(42);
+        for _ in 0..100 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+        assert_ne!(SimpleRng::new(1).next_u64(), SimpleRng::new(2).next_u64());
+    }
+
+    #[test]
+    fn jitter_stays_in_bounds() {
+        let mut rng = SimpleRng::new(7);
+        let mut seen = std::collections::HashSet::new();
+        for _ in 0..1000 {
+            let j = rng.jitter(3);
+            assert!((-3..=3).contains(&j), "{} out of range", j);
+            seen.insert(j);
+        }
+        assert_eq!(seen.len(), 7);
+        assert_eq!(rng.jitter(0), 0);
+        assert_eq!(rng.jitter(-5), 0);
+    }
+
+    #[test]
+    fn unit_stays_in_bounds() {
+        let mut rng = SimpleRng::new(7);
+        for _ in 0..1000 {
+            let u = rng.unit();
+            assert!((-1.0..1.0).contains(&u), "{} out of range", u);
+        }
+    }
+
+    #[test]
+    fn sanitize_replaces_illegal_characters() {
+        assert_eq!(sanitize_filename(r#"a<b>c:d"e/f\g|h?i*j k"#), "a_b_c_d_e_f_g_h_i_j_k");
+        assert_eq!(sanitize_filename("tab\there\n"), "tab_there_");
+        assert_eq!(sanitize_filename("Front Door"), "Front_Door");
+    }
+
+    #[test]
+    fn sanitize_caps_length() {
+        let long = "x".repeat(150);
+        assert_eq!(sanitize_filename(&long).chars().count(), MAX_FILENAME_CHARS);
+        // Multi-byte characters count as one each
+        assert_eq!(sanitize_filename(&"é".repeat(150)).chars().count(), MAX_FILENAME_CHARS);
+    }
+
+    #[test]
+    fn sanitize_trims_trailing_dots() {
+        assert_eq!(sanitize_filename("clip..."), "clip");
+        // Spaces become '_' first, so only the dot run at the very end is trimmed
+        assert_eq!(sanitize_filename("clip. ."), "clip._");
+        assert_eq!(sanitize_filename("..."), "unnamed");
+        assert_eq!(sanitize_filename(""), "unnamed");
+    }
+
+    #[test]
+    fn sanitize_prefixes_reserved_names() {
+        assert_eq!(sanitize_filename("CON"), "_CON");
+        assert_eq!(sanitize_filename("nul.txt"), "_nul.txt");
+        assert_eq!(sanitize_filename("com1.tar.gz"), "_com1.tar.gz");
+        assert_eq!(sanitize_filename("CONSOLE"), "CONSOLE");
+
+        let reserved = format!("con.{}", "x".repeat(150));
+        assert_eq!(sanitize_filename(&reserved).chars().count(), MAX_FILENAME_CHARS);
+    }
+}
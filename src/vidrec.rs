@@ -6,7 +6,9 @@
 // License: GPLv2
 // ============================================================================
 
-use std::sync::{Arc, Mutex};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{Sender, Receiver, channel};
 use std::thread::{self, JoinHandle};
 use std::time::{Duration, Instant};
@@ -14,12 +16,19 @@ use std::path::{Path, PathBuf};
 
 use chrono::Local;
 use log::{info, error, warn};
+use serde::Serialize;
+
+use crate::paths;
+use crate::util::{available_space, sanitize_filename};
+#[cfg(feature = "video")]
+use crate::util::SimpleRng;
 
 #[cfg(feature = "video")]
 use opencv::{
     prelude::*,
     videoio::{self, VideoCapture, VideoWriter, CAP_ANY},
-    core::{Size, Vector},
+    core::{self, Rect, Size, Vector},
+    imgcodecs, imgproc,
     Result as CvResult,
 };
 
@@ -30,7 +39,53 @@ use opencv::{
 const DEFAULT_FPS: f64 = 30.0;
 const DEFAULT_WIDTH: i32 = 1920;
 const DEFAULT_HEIGHT: i32 = 1080;
-const DEFAULT_OUTPUT_DIR: &str = "recordings";
+const DEFAULT_FIRST_FRAME_TIMEOUT_SEC: u64 = 15;
+/// Longest pause between connection attempts
+#[cfg(feature = "video")]
+const RECONNECT_BACKOFF_MAX_SEC: u64 = 30;
+/// Environment variable OpenCV's FFmpeg backend reads capture options from
+const FFMPEG_CAPTURE_OPTIONS_ENV: &str = "OPENCV_FFMPEG_CAPTURE_OPTIONS";
+const DEFAULT_RECONNECT_AFTER_FAILED_READS: u32 = 30;
+const DEFAULT_MAX_RECONNECT_ATTEMPTS: u32 = 10;
+/// Minimum time between "failed/empty read" warnings while a source misbehaves
+#[cfg(feature = "video")]
+const READ_WARNING_INTERVAL: Duration = Duration::from_secs(5);
+const DEFAULT_MAX_EMPTY_FRAMES: u32 = 100;
+/// Consecutive failed/empty reads that mean a video file source has ended
+#[cfg(feature = "video")]
+const VIDEO_FILE_EOF_READS: u32 = 3;
+/// Webcam indices probed by `VideoRecorder::enumerate_webcams`
+#[cfg(feature = "video")]
+const MAX_WEBCAM_INDEX: i32 = 9;
+const DEFAULT_SUB_STREAM_WIDTH: i32 = 640;
+const DEFAULT_SUB_STREAM_HEIGHT: i32 = 360;
+const DEFAULT_PREVIEW_WIDTH: i32 = 320;
+#[cfg(feature = "video")]
+const PREVIEW_JPEG_QUALITY: i32 = 70;
+#[cfg(feature = "video")]
+const SNAPSHOT_JPEG_QUALITY: i32 = 90;
+const DEFAULT_MOTION_THRESHOLD: f64 = 8.0;
+const DEFAULT_MOTION_COOLDOWN_SEC: u64 = 5;
+/// Hard cap on pre-record frames regardless of fps (~1.8 GB at 1080p)
+#[cfg(feature = "video")]
+const MAX_PREBUFFER_FRAMES: usize = 300;
+#[cfg(feature = "video")]
+const OVERLAY_MARGIN_PX: i32 = 10;
+/// Scale factors tried, largest first, when the writer rejects the camera's
+/// own frame size; the aspect ratio is kept and dimensions rounded down to even
+#[cfg(feature = "video")]
+const WRITER_FALLBACK_SCALES: [f64; 2] = [0.75, 0.5];
+const DEFAULT_MIN_FREE_SPACE_MB: u64 = 500;
+/// Frames between free-space checks while recording
+#[cfg(feature = "video")]
+const DISK_CHECK_INTERVAL_FRAMES: u64 = 300;
+/// How long `capture_snapshot` waits for the recording thread
+const SNAPSHOT_TIMEOUT: Duration = Duration::from_secs(5);
+#[cfg(feature = "video")]
+const OVERLAY_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+/// Per-pixel grayscale delta counted as "changed" when locating motion
+#[cfg(feature = "video")]
+const MOTION_PIXEL_THRESHOLD: f64 = 25.0;
 
 // ============================================================================
 // VIDEO FORMATS
@@ -41,21 +96,43 @@ pub enum VideoFormat {
     MP4,
     AVI,
     MKV,
+    /// Pick a container/codec from the camera source and its negotiated codec
+    Auto,
 }
 
 impl VideoFormat {
     pub fn extension(&self) -> &str {
         match self {
-            VideoFormat::MP4 => "mp4",
+            VideoFormat::MP4 | VideoFormat::Auto => "mp4",
             VideoFormat::AVI => "avi",
             VideoFormat::MKV => "mkv",
         }
     }
 
+    /// Choose a concrete format for a source. `source_codec` is the FourCC the
+    /// capture reported (may be empty). Returns the format and the reason.
+    pub fn auto_select(source: &CameraSource, source_codec: &str) -> (VideoFormat, String) {
+        let codec = source_codec.to_ascii_uppercase();
+
+        if ["H264", "AVC1", "X264", "HEVC", "HEV1", "H265"].contains(&codec.as_str()) {
+            return (VideoFormat::MKV, format!("source delivers {} - MKV handles long H.264/H.265 streams", codec));
+        }
+        if codec == "MJPG" {
+            return (VideoFormat::AVI, "source delivers MJPEG - AVI/MJPG keeps frames intact".to_string());
+        }
+
+        match source {
+            CameraSource::RtspStream(_) => (VideoFormat::MKV, "RTSP stream - MKV survives interrupted streams".to_string()),
+            CameraSource::HttpStream(_) => (VideoFormat::AVI, "HTTP stream (usually MJPEG) - AVI/MJPG".to_string()),
+            CameraSource::Webcam(_) => (VideoFormat::MP4, "webcam - MP4 for broad playback support".to_string()),
+            CameraSource::VideoFile(_) => (VideoFormat::MP4, "video file - MP4 for broad playback support".to_string()),
+        }
+    }
+
     #[cfg(feature = "video")]
     pub fn fourcc(&self) -> i32 {
         match self {
-            VideoFormat::MP4 => VideoWriter::fourcc('m' as i8, 'p' as i8, '4' as i8, 'v' as i8).unwrap(),
+            VideoFormat::MP4 | VideoFormat::Auto => VideoWriter::fourcc('m' as i8, 'p' as i8, '4' as i8, 'v' as i8).unwrap(),
             VideoFormat::AVI => VideoWriter::fourcc('M' as i8, 'J' as i8, 'P' as i8, 'G' as i8).unwrap(),
             VideoFormat::MKV => VideoWriter::fourcc('X' as i8, '2' as i8, '6' as i8, '4' as i8).unwrap(),
         }
@@ -67,6 +144,16 @@ impl VideoFormat {
     }
 }
 
+/// Decode a FourCC integer (as reported by CAP_PROP_FOURCC) into its text form
+#[cfg(feature = "video")]
+fn fourcc_to_string(code: i32) -> String {
+    code.to_le_bytes()
+        .iter()
+        .map(|&b| b as char)
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect()
+}
+
 // ============================================================================
 // CAMERA TYPES
 // ============================================================================
@@ -84,6 +171,21 @@ pub enum CameraSource {
 }
 
 impl CameraSource {
+    /// Source for a command-line location: a webcam index, an `rtsp://` or
+    /// `http(s)://` URL, otherwise a video file path
+    pub fn parse(location: &str) -> Self {
+        let lower = location.to_ascii_lowercase();
+        if let Ok(idx) = location.parse::<i32>() {
+            CameraSource::Webcam(idx)
+        } else if lower.starts_with("rtsp://") || lower.starts_with("rtsps://") {
+            CameraSource::RtspStream(location.to_string())
+        } else if lower.starts_with("http://") || lower.starts_with("https://") {
+            CameraSource::HttpStream(location.to_string())
+        } else {
+            CameraSource::VideoFile(location.to_string())
+        }
+    }
+
     #[cfg(feature = "video")]
     fn to_opencv_string(&self) -> String {
         match self {
@@ -94,6 +196,11 @@ impl CameraSource {
         }
     }
 
+    /// True for network sources that can drop and be re-opened mid-recording
+    pub fn is_network_stream(&self) -> bool {
+        matches!(self, CameraSource::RtspStream(_) | CameraSource::HttpStream(_))
+    }
+
     pub fn source_type(&self) -> &str {
         match self {
             CameraSource::Webcam(_) => "Webcam",
@@ -102,12 +209,129 @@ impl CameraSource {
             CameraSource::VideoFile(_) => "File",
         }
     }
+
+    /// Error for a source that was created but reports as not opened. A webcam
+    /// the OS still lists is almost always held by another application.
+    #[cfg(feature = "video")]
+    fn open_failure(&self) -> String {
+        let CameraSource::Webcam(idx) = self else {
+            return format!("Failed to open {} source", self.source_type());
+        };
+        match webcam_listed(*idx) {
+            Some(true) => format!("Camera is in use by another application (webcam {})", idx),
+            Some(false) => format!("Webcam {} not found", idx),
+            None => format!(
+                "Webcam {} failed to open (it may be in use by another application)",
+                idx
+            ),
+        }
+    }
+}
+
+/// Whether the OS lists webcam `idx` as present, where that can be checked
+/// cheaply (Linux: `/dev/video<idx>`). None means unknown.
+#[cfg(feature = "video")]
+fn webcam_listed(idx: i32) -> Option<bool> {
+    if cfg!(target_os = "linux") {
+        Some(Path::new(&format!("/dev/video{}", idx)).exists())
+    } else {
+        None
+    }
 }
 
 // ============================================================================
 // CAMERA INFO
 // ============================================================================
 
+/// Static, user-supplied description of where a camera is installed
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CameraMetadata {
+    /// Site or property name (e.g. "Warehouse North")
+    pub site: Option<String>,
+    /// Free-form location on site (e.g. "Loading dock, east wall")
+    pub location: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+}
+
+impl CameraMetadata {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Human-readable lines for upload descriptions and reports
+    pub fn description(&self) -> String {
+        let mut lines = Vec::new();
+        if let Some(site) = &self.site {
+            lines.push(format!("Site: {}", site));
+        }
+        if let Some(location) = &self.location {
+            lines.push(format!("Location: {}", location));
+        }
+        if let (Some(lat), Some(lon)) = (self.latitude, self.longitude) {
+            lines.push(format!("GPS: {:.6}, {:.6}", lat, lon));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Stream properties reported by a camera when probed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraProbe {
+    pub width: i32,
+    pub height: i32,
+    pub fps: f64,
+}
+
+/// Transport FFmpeg uses for RTSP sources
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RtspTransport {
+    /// Leave the choice to FFmpeg (UDP, falling back to TCP)
+    #[default]
+    Default,
+    /// Interleave RTP over the RTSP connection; robust on lossy/congested links
+    Tcp,
+    Udp,
+}
+
+impl RtspTransport {
+    /// Value for `OPENCV_FFMPEG_CAPTURE_OPTIONS`, None to leave it untouched
+    fn ffmpeg_options(self) -> Option<&'static str> {
+        match self {
+            RtspTransport::Default => None,
+            RtspTransport::Tcp => Some("rtsp_transport;tcp"),
+            RtspTransport::Udp => Some("rtsp_transport;udp"),
+        }
+    }
+}
+
+/// Transport chosen with [`set_rtsp_transport`]; unset means FFmpeg's default
+static PROCESS_RTSP_TRANSPORT: OnceLock<RtspTransport> = OnceLock::new();
+
+/// Select the RTSP transport for every capture this process opens. OpenCV only
+/// reads FFmpeg capture options from the environment, so this is process-wide
+/// and can be set once; later calls are ignored.
+///
+/// # Safety
+///
+/// Must be called before any other thread is spawned (including recorder,
+/// GUI and signal-handler threads): it writes the process environment, which
+/// is only sound while no other thread can read it.
+pub unsafe fn set_rtsp_transport(transport: RtspTransport) {
+    if PROCESS_RTSP_TRANSPORT.set(transport).is_err() {
+        return;
+    }
+    if let Some(options) = transport.ffmpeg_options() {
+        // SAFETY: the caller guarantees the process is still single-threaded
+        unsafe { std::env::set_var(FFMPEG_CAPTURE_OPTIONS_ENV, options) };
+    }
+}
+
+/// The transport selected with [`set_rtsp_transport`]
+pub fn rtsp_transport() -> RtspTransport {
+    PROCESS_RTSP_TRANSPORT.get().copied().unwrap_or_default()
+}
+
 #[derive(Debug, Clone)]
 pub struct CameraInfo {
     pub name: String,
@@ -115,6 +339,11 @@ pub struct CameraInfo {
     pub width: i32,
     pub height: i32,
     pub fps: f64,
+    pub metadata: CameraMetadata,
+    /// Transport this camera needs; only used for `CameraSource::RtspStream`.
+    /// It must match the process-wide [`set_rtsp_transport`] choice, otherwise
+    /// a warning is logged and the process-wide one is used.
+    pub rtsp_transport: RtspTransport,
 }
 
 impl CameraInfo {
@@ -125,6 +354,8 @@ impl CameraInfo {
             width: DEFAULT_WIDTH,
             height: DEFAULT_HEIGHT,
             fps: DEFAULT_FPS,
+            metadata: CameraMetadata::default(),
+            rtsp_transport: RtspTransport::default(),
         }
     }
 
@@ -138,29 +369,259 @@ impl CameraInfo {
         self.fps = fps;
         self
     }
+
+    pub fn with_metadata(mut self, metadata: CameraMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    pub fn with_rtsp_transport(mut self, transport: RtspTransport) -> Self {
+        self.rtsp_transport = transport;
+        self
+    }
+
+    /// Describe this camera's low-resolution sub-stream at `source`. The result
+    /// shares the metadata, is named "<name> (sub)" and defaults to 640x360.
+    pub fn sub_stream(&self, source: CameraSource) -> CameraInfo {
+        CameraInfo::new(format!("{} (sub)", self.name), source)
+            .with_resolution(DEFAULT_SUB_STREAM_WIDTH, DEFAULT_SUB_STREAM_HEIGHT)
+            .with_fps(self.fps)
+            .with_metadata(self.metadata.clone())
+            .with_rtsp_transport(self.rtsp_transport)
+    }
+
+    /// Take resolution and fps from a probe result. Call `with_resolution` /
+    /// `with_fps` afterwards to override individual values.
+    pub fn with_probe(mut self, probe: &CameraProbe) -> Self {
+        self.width = probe.width;
+        self.height = probe.height;
+        self.fps = probe.fps;
+        self
+    }
+
+    /// Open the source, read one frame and report what the camera actually
+    /// delivers. The frame size is authoritative; fps falls back to the
+    /// configured value when the backend doesn't report one.
+    #[cfg(feature = "video")]
+    pub fn probe(&self) -> Result<CameraProbe, String> {
+        let mut camera = open_capture(&self.source, false, self.rtsp_transport)?;
+        if !camera.is_opened().map_err(|e| e.to_string())? {
+            return Err(self.source.open_failure());
+        }
+
+        let mut frame = Mat::default();
+        let read = camera.read(&mut frame).map_err(|e| format!("Error reading frame: {}", e))?;
+        if !read || frame.empty() {
+            let _ = camera.release();
+            return Err("Camera opened but delivered no frames".to_string());
+        }
+
+        let fps = camera.get(videoio::CAP_PROP_FPS).unwrap_or(0.0);
+        let _ = camera.release();
+
+        Ok(CameraProbe {
+            width: frame.cols(),
+            height: frame.rows(),
+            fps: if fps.is_finite() && fps > 0.0 { fps } else { self.fps },
+        })
+    }
+
+    /// Stub when the video feature is disabled
+    #[cfg(not(feature = "video"))]
+    pub fn probe(&self) -> Result<CameraProbe, String> {
+        Err("Video recording feature not enabled. Build with --features video".to_string())
+    }
+}
+
+/// Open a capture for any camera source. With `hw_accel`, stream and file
+/// sources are opened through FFmpeg with hardware decoding requested,
+/// falling back to software decoding if that fails. RTSP sources use the
+/// process-wide transport; a camera asking for another one gets a warning.
+#[cfg(feature = "video")]
+fn open_capture(source: &CameraSource, hw_accel: bool, transport: RtspTransport) -> Result<VideoCapture, String> {
+    if matches!(source, CameraSource::RtspStream(_))
+        && transport != RtspTransport::Default
+        && transport != rtsp_transport()
+    {
+        warn!(
+            "RTSP transport {:?} requested but the process uses {:?}; call set_rtsp_transport at startup",
+            transport,
+            rtsp_transport()
+        );
+    }
+
+    if hw_accel && !matches!(source, CameraSource::Webcam(_)) {
+        let params = Vector::<i32>::from_slice(&[
+            videoio::CAP_PROP_HW_ACCELERATION,
+            videoio::VIDEO_ACCELERATION_ANY,
+        ]);
+        match VideoCapture::from_file_with_params(&source.to_opencv_string(), videoio::CAP_FFMPEG, &params) {
+            Ok(cam) if cam.is_opened().unwrap_or(false) => {
+                let mode = cam.get(videoio::CAP_PROP_HW_ACCELERATION).unwrap_or(0.0) as i32;
+                if mode == videoio::VIDEO_ACCELERATION_NONE {
+                    warn!("Hardware decoding unavailable, FFmpeg is decoding in software");
+                } else {
+                    info!("Hardware decoding enabled (mode {})", mode);
+                }
+                return Ok(cam);
+            }
+            Ok(_) => warn!("Hardware-accelerated open failed, falling back to software decoding"),
+            Err(e) => warn!("Hardware-accelerated open failed ({}), falling back to software decoding", e),
+        }
+    }
+
+    match source {
+        CameraSource::Webcam(idx) => VideoCapture::new(*idx, CAP_ANY)
+            .map_err(|e| format!("Failed to open webcam {}: {}", idx, e)),
+        _ => VideoCapture::from_file(&source.to_opencv_string(), CAP_ANY)
+            .map_err(|e| format!("Failed to open camera source: {}", e)),
+    }
 }
 
 // ============================================================================
 // VIDEO CONFIGURATION
 // ============================================================================
 
+/// Which output gets the text overlay
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverlayTarget {
+    #[default]
+    Recording,
+    Preview,
+    Both,
+}
+
+impl OverlayTarget {
+    #[cfg(feature = "video")]
+    fn recording(self) -> bool {
+        matches!(self, OverlayTarget::Recording | OverlayTarget::Both)
+    }
+
+    #[cfg(feature = "video")]
+    fn preview(self) -> bool {
+        matches!(self, OverlayTarget::Preview | OverlayTarget::Both)
+    }
+}
+
+/// Frame corner used for the text overlay
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverlayCorner {
+    TopLeft,
+    TopRight,
+    #[default]
+    BottomLeft,
+    BottomRight,
+}
+
 #[derive(Debug, Clone)]
 pub struct VideoConfig {
     pub output_dir: PathBuf,
+    /// Subdirectory of `output_dir` for each segment, e.g. `{camera}/{date}`;
+    /// see `with_subdir_template`. None = directly in `output_dir`.
+    pub subdir_template: Option<String>,
     pub format: VideoFormat,
+    /// Writer codec override (e.g. `H264`, `HEVC`); None = the format's default
+    pub fourcc: Option<[u8; 4]>,
     pub max_duration_sec: Option<u64>,
+    /// End the segment once the file reaches this size (checked every 100 frames)
     pub max_file_size_mb: Option<u64>,
+    /// Refuse to start, or stop and finalize the file, when free space on the
+    /// output volume drops below this
+    pub min_free_space_mb: Option<u64>,
+    /// Start a new segment (new file, same camera handle) when the duration
+    /// or size limit ends one. A stop request always ends recording.
     pub auto_restart: bool,
+    /// Request hardware video decoding (FFmpeg) for stream and file sources
+    pub hw_accel: bool,
+    /// Frames OpenCV may queue inside the capture (`CAP_PROP_BUFFERSIZE`);
+    /// 1 keeps RTSP latency lowest. None = backend default.
+    pub capture_buffer_size: Option<u32>,
+    /// Keep retrying the connection (capped backoff) until it succeeds or is stopped
+    pub reconnect_forever: bool,
+    /// Extra attempts (with backoff) for the initial open before giving up
+    pub open_retries: u32,
+    /// Fail if the camera opens but delivers no frame within this many seconds
+    pub first_frame_timeout_sec: Option<u64>,
+    /// Consecutive failed reads after which an RTSP/HTTP stream is re-opened (0 = never).
+    /// Empty frames count as failed reads.
+    pub reconnect_after_failed_reads: u32,
+    /// Consecutive empty frames after which a webcam recording fails (0 = never);
+    /// network streams are reconnected instead
+    pub max_empty_frames: u32,
+    /// Re-open attempts before a dropped stream is reported as an error
+    /// (unlimited with `reconnect_forever`)
+    pub max_reconnect_attempts: u32,
+    /// Delete this camera's segments older than this many hours (rolling buffer)
+    pub segment_retention_hours: Option<u64>,
+    /// Pause before opening the next segment on auto-restart
+    pub restart_delay_ms: u64,
+    /// Random extra pause (0..=jitter, rounded down to even) added to `restart_delay_ms`
+    pub restart_jitter_ms: u64,
+    /// Seed for the restart jitter, for reproducible pauses (None = clock)
+    pub restart_seed: Option<u64>,
+    /// Rate of downscaled JPEG preview frames sent to the GUI (None = off)
+    pub preview_fps: Option<f64>,
+    pub preview_width: i32,
+    /// Save a still image whenever motion is detected
+    pub snapshot_on_motion: bool,
+    /// Mean grayscale difference (0-255) between frames that counts as motion
+    pub motion_threshold: f64,
+    /// Minimum seconds between motion snapshots
+    pub snapshot_interval_sec: u64,
+    /// Burn the current date/time into every recorded frame
+    pub timestamp_overlay: bool,
+    /// Label frames with the camera name and its state (REC/PAUSED/STANDBY)
+    pub name_overlay: bool,
+    /// Whether the overlay goes on recorded frames, preview frames or both
+    pub overlay_target: OverlayTarget,
+    pub overlay_corner: OverlayCorner,
+    /// OpenCV font scale (1.0 is roughly 22px tall); shrunk to fit narrow frames
+    pub overlay_font_scale: f64,
+    /// Only write frames while motion is detected (plus `motion_cooldown_sec`)
+    pub motion_recording: bool,
+    /// Seconds to keep writing after the last motion
+    pub motion_cooldown_sec: u64,
+    /// Seconds of frames kept while armed or waiting for motion, written
+    /// ahead of the first recorded frame (None = off)
+    pub prebuffer_sec: Option<u64>,
 }
 
 impl Default for VideoConfig {
     fn default() -> Self {
         Self {
-            output_dir: PathBuf::from(DEFAULT_OUTPUT_DIR),
+            output_dir: paths::recordings_dir(),
+            subdir_template: None,
             format: VideoFormat::MP4,
+            fourcc: None,
             max_duration_sec: Some(3600), // 1 hour
             max_file_size_mb: Some(2048), // 2GB
+            min_free_space_mb: Some(DEFAULT_MIN_FREE_SPACE_MB),
             auto_restart: true,
+            hw_accel: false,
+            capture_buffer_size: None,
+            reconnect_forever: false,
+            open_retries: 0,
+            first_frame_timeout_sec: Some(DEFAULT_FIRST_FRAME_TIMEOUT_SEC),
+            reconnect_after_failed_reads: DEFAULT_RECONNECT_AFTER_FAILED_READS,
+            max_empty_frames: DEFAULT_MAX_EMPTY_FRAMES,
+            max_reconnect_attempts: DEFAULT_MAX_RECONNECT_ATTEMPTS,
+            segment_retention_hours: None,
+            restart_delay_ms: 0,
+            restart_jitter_ms: 0,
+            restart_seed: None,
+            preview_fps: None,
+            preview_width: DEFAULT_PREVIEW_WIDTH,
+            snapshot_on_motion: false,
+            motion_threshold: DEFAULT_MOTION_THRESHOLD,
+            snapshot_interval_sec: 5,
+            timestamp_overlay: false,
+            name_overlay: false,
+            overlay_target: OverlayTarget::default(),
+            overlay_corner: OverlayCorner::default(),
+            overlay_font_scale: 1.0,
+            motion_recording: false,
+            motion_cooldown_sec: DEFAULT_MOTION_COOLDOWN_SEC,
+            prebuffer_sec: None,
         }
     }
 }
@@ -175,11 +636,61 @@ impl VideoConfig {
         self
     }
 
+    /// Place each segment in a subdirectory of the output dir, created on
+    /// demand. Placeholders: `{camera}` (sanitized name), `{date}` (YYYY-MM-DD),
+    /// `{year}`, `{month}`, `{day}`. E.g. `"{camera}/{date}"`.
+    pub fn with_subdir_template(mut self, template: impl Into<String>) -> Self {
+        let template = template.into();
+        let template = template.trim_matches(|c| c == '/' || c == '\\');
+        self.subdir_template = (!template.is_empty()).then(|| template.to_string());
+        self
+    }
+
+    /// Directory a segment of `camera_name` started at `now` is written to
+    pub fn segment_dir(&self, camera_name: &str, now: chrono::DateTime<Local>) -> PathBuf {
+        let Some(template) = &self.subdir_template else {
+            return self.output_dir.clone();
+        };
+
+        let subdir = template
+            .replace("{camera}", &sanitize_filename(camera_name))
+            .replace("{date}", &now.format("%Y-%m-%d").to_string())
+            .replace("{year}", &now.format("%Y").to_string())
+            .replace("{month}", &now.format("%m").to_string())
+            .replace("{day}", &now.format("%d").to_string());
+
+        // Never let a template climb out of the output dir
+        subdir
+            .split(['/', '\\'])
+            .filter(|part| !part.is_empty() && *part != "." && *part != "..")
+            .fold(self.output_dir.clone(), |dir, part| dir.join(part))
+    }
+
+    /// How many directory levels below `output_dir` segments can be in
+    #[cfg(feature = "video")]
+    fn subdir_depth(&self) -> usize {
+        self.subdir_template
+            .as_ref()
+            .map_or(0, |t| t.split(['/', '\\']).filter(|p| !p.is_empty()).count())
+    }
+
     pub fn with_format(mut self, format: VideoFormat) -> Self {
         self.format = format;
         self
     }
 
+    /// Override the writer codec with any four-character code (e.g. "H264")
+    pub fn with_fourcc(mut self, code: &str) -> Result<Self, String> {
+        let bytes: [u8; 4] = code
+            .as_bytes()
+            .try_into()
+            .ok()
+            .filter(|b: &[u8; 4]| b.iter().all(|c| c.is_ascii_graphic() || *c == b' '))
+            .ok_or_else(|| format!("Invalid FourCC '{}': expected exactly four ASCII characters", code))?;
+        self.fourcc = Some(bytes);
+        Ok(self)
+    }
+
     pub fn with_max_duration(mut self, seconds: u64) -> Self {
         self.max_duration_sec = Some(seconds);
         self
@@ -190,10 +701,292 @@ impl VideoConfig {
         self
     }
 
+    /// Minimum free space on the output volume (0 = don't check)
+    pub fn with_min_free_space(mut self, megabytes: u64) -> Self {
+        self.min_free_space_mb = (megabytes > 0).then_some(megabytes);
+        self
+    }
+
+    /// Error if the output volume has less than `min_free_space_mb` free.
+    /// Passes when the free space can't be determined.
+    fn check_free_space(&self) -> Result<(), String> {
+        let Some(min_mb) = self.min_free_space_mb else {
+            return Ok(());
+        };
+        match available_space(&self.output_dir) {
+            Some(free) if free < min_mb.saturating_mul(1_048_576) => Err(format!(
+                "Only {} MB free in {} (minimum {} MB)",
+                free / 1_048_576,
+                self.output_dir.display(),
+                min_mb
+            )),
+            _ => Ok(()),
+        }
+    }
+
     pub fn with_auto_restart(mut self, restart: bool) -> Self {
         self.auto_restart = restart;
         self
     }
+
+    /// Never give up connecting to this camera (e.g. entrance/doorbell cameras);
+    /// only an explicit stop ends the retries
+    pub fn with_reconnect_forever(mut self, enabled: bool) -> Self {
+        self.reconnect_forever = enabled;
+        self
+    }
+
+    /// Retry a failed initial open up to `retries` times (1s, 2s, 4s... apart),
+    /// e.g. for USB webcams that need a second try after sleep
+    pub fn with_open_retries(mut self, retries: u32) -> Self {
+        self.open_retries = retries;
+        self
+    }
+
+    /// Seconds to wait for the first frame after opening (0 = wait forever).
+    /// Catches sources that connect but never stream, e.g. a wrong RTSP path.
+    pub fn with_first_frame_timeout(mut self, seconds: u64) -> Self {
+        self.first_frame_timeout_sec = (seconds > 0).then_some(seconds);
+        self
+    }
+
+    /// Limit the capture's internal frame queue (e.g. 1) so the freshest frame
+    /// is always read. Not every backend supports this; the result is logged.
+    pub fn with_capture_buffer_size(mut self, frames: u32) -> Self {
+        self.capture_buffer_size = (frames > 0).then_some(frames);
+        self
+    }
+
+    /// Fail a webcam recording after `frames` consecutive empty frames (0 = keep trying)
+    pub fn with_max_empty_frames(mut self, frames: u32) -> Self {
+        self.max_empty_frames = frames;
+        self
+    }
+
+    /// Re-open a dropped RTSP/HTTP stream after `failed_reads` consecutive failed
+    /// reads (0 = never), giving up after `max_attempts` tries. Recording resumes
+    /// in the same file.
+    pub fn with_stream_reconnect(mut self, failed_reads: u32, max_attempts: u32) -> Self {
+        self.reconnect_after_failed_reads = failed_reads;
+        self.max_reconnect_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Decode RTSP/HTTP/file sources on the GPU when available
+    pub fn with_hw_accel(mut self, enabled: bool) -> Self {
+        self.hw_accel = enabled;
+        self
+    }
+
+    /// Keep a rolling buffer: whenever a segment starts, delete this camera's
+    /// recordings (and sidecars) older than `hours`. Pair with `with_max_duration`
+    /// and `with_auto_restart(true)` for DVR-style fixed-length segments.
+    pub fn with_segment_retention(mut self, hours: u64) -> Self {
+        self.segment_retention_hours = (hours > 0).then_some(hours);
+        self
+    }
+
+    /// Pause `delay_ms` (plus up to `jitter_ms` extra) between auto-restart segments
+    pub fn with_restart_delay(mut self, delay_ms: u64, jitter_ms: u64) -> Self {
+        self.restart_delay_ms = delay_ms;
+        self.restart_jitter_ms = jitter_ms;
+        self
+    }
+
+    /// Seed the restart jitter so the pauses repeat run to run. Cameras
+    /// sharing a seed restart in step, so give each its own.
+    pub fn with_restart_seed(mut self, seed: u64) -> Self {
+        self.restart_seed = Some(seed);
+        self
+    }
+
+    /// Pause to apply before the next segment, including jitter
+    #[cfg(feature = "video")]
+    fn restart_pause(&self, rng: &mut SimpleRng) -> Duration {
+        // Center the ±jitter on half the range to get 0..=jitter (rounded down to even)
+        let half = (self.restart_jitter_ms / 2).min(i32::MAX as u64) as i32;
+        let jitter = (half + rng.jitter(half)) as u64;
+        Duration::from_millis(self.restart_delay_ms + jitter)
+    }
+
+    /// Stream preview frames at `fps` (e.g. 2.0) while armed or recording
+    pub fn with_preview(mut self, fps: f64) -> Self {
+        self.preview_fps = if fps > 0.0 { Some(fps) } else { None };
+        self
+    }
+
+    pub fn with_preview_width(mut self, width: i32) -> Self {
+        self.preview_width = width.max(16);
+        self
+    }
+
+    /// Save a JPEG to `<output_dir>/snapshots/<date>/` each time motion above
+    /// `threshold` is detected, at most once every `min_interval_sec`
+    pub fn with_motion_snapshots(mut self, threshold: f64, min_interval_sec: u64) -> Self {
+        self.snapshot_on_motion = true;
+        self.motion_threshold = threshold.max(0.1);
+        self.snapshot_interval_sec = min_interval_sec;
+        self
+    }
+
+    /// Record only while motion above `threshold` is detected, continuing for
+    /// the cooldown after it stops; other frames are discarded. Shares the
+    /// threshold with motion snapshots.
+    pub fn with_motion_detection(mut self, enabled: bool, threshold: f64) -> Self {
+        self.motion_recording = enabled;
+        self.motion_threshold = threshold.max(0.1);
+        self
+    }
+
+    pub fn with_motion_cooldown(mut self, seconds: u64) -> Self {
+        self.motion_cooldown_sec = seconds;
+        self
+    }
+
+    /// Keep the last `seconds` of frames (at most `MAX_PREBUFFER_FRAMES`) so
+    /// motion-triggered and armed recordings include the lead-up (0 = off)
+    pub fn with_prebuffer_sec(mut self, seconds: u64) -> Self {
+        self.prebuffer_sec = (seconds > 0).then_some(seconds);
+        self
+    }
+
+    /// Draw the current date/time onto each recorded frame (bottom-left by default)
+    pub fn with_timestamp_overlay(mut self, enabled: bool) -> Self {
+        self.timestamp_overlay = enabled;
+        self
+    }
+
+    /// Add the camera name and a REC/PAUSED/STANDBY indicator to the overlay
+    pub fn with_name_overlay(mut self, enabled: bool) -> Self {
+        self.name_overlay = enabled;
+        self
+    }
+
+    pub fn with_overlay_target(mut self, target: OverlayTarget) -> Self {
+        self.overlay_target = target;
+        self
+    }
+
+    /// Overlay text for a frame, or None if no overlay is enabled
+    #[cfg(feature = "video")]
+    fn overlay_text(&self, camera: &str, indicator: &str) -> Option<String> {
+        let mut parts = Vec::new();
+        if self.name_overlay {
+            parts.push(format!("{} [{}]", camera, indicator));
+        }
+        if self.timestamp_overlay {
+            parts.push(Local::now().format(OVERLAY_TIME_FORMAT).to_string());
+        }
+        (!parts.is_empty()).then(|| parts.join("  "))
+    }
+
+    pub fn with_overlay_position(mut self, corner: OverlayCorner) -> Self {
+        self.overlay_corner = corner;
+        self
+    }
+
+    pub fn with_overlay_font_scale(mut self, scale: f64) -> Self {
+        self.overlay_font_scale = scale.max(0.1);
+        self
+    }
+}
+
+// ============================================================================
+// MOTION DETECTION
+// ============================================================================
+
+/// Result of comparing a frame with the previous one
+#[cfg(feature = "video")]
+struct MotionResult {
+    /// Mean absolute grayscale difference (0-255)
+    score: f64,
+    /// Bounding box of the changed area
+    region: Rect,
+}
+
+/// Frame-to-frame motion detector (grayscale absdiff + mean)
+#[cfg(feature = "video")]
+struct MotionDetector {
+    previous: Option<Mat>,
+    threshold: f64,
+}
+
+#[cfg(feature = "video")]
+impl MotionDetector {
+    fn new(threshold: f64) -> Self {
+        Self {
+            previous: None,
+            threshold,
+        }
+    }
+
+    /// Feed the next frame; returns Some when motion exceeds the threshold
+    fn update(&mut self, frame: &Mat) -> CvResult<Option<MotionResult>> {
+        let mut gray = Mat::default();
+        imgproc::cvt_color(frame, &mut gray, imgproc::COLOR_BGR2GRAY, 0)?;
+        let mut blurred = Mat::default();
+        imgproc::gaussian_blur(&gray, &mut blurred, Size::new(21, 21), 0.0, 0.0, core::BORDER_DEFAULT)?;
+
+        let previous = match self.previous.replace(blurred) {
+            // Resolution changes reset the baseline
+            Some(prev) if prev.size()? == frame.size()? => prev,
+            _ => return Ok(None),
+        };
+        let current = self.previous.as_ref().unwrap();
+
+        let mut diff = Mat::default();
+        core::absdiff(&previous, current, &mut diff)?;
+        let score = core::mean(&diff, &core::no_array())?[0];
+
+        if score < self.threshold {
+            return Ok(None);
+        }
+
+        let mut mask = Mat::default();
+        imgproc::threshold(&diff, &mut mask, MOTION_PIXEL_THRESHOLD, 255.0, imgproc::THRESH_BINARY)?;
+        let region = imgproc::bounding_rect(&mask)?;
+
+        Ok(Some(MotionResult { score, region }))
+    }
+}
+
+// ============================================================================
+// SIDECAR
+// ============================================================================
+
+/// JSON written next to each finished recording (`<file>.json`)
+#[cfg(feature = "video")]
+#[derive(Debug, Serialize)]
+struct RecordingSidecar<'a> {
+    camera: &'a str,
+    source: &'a str,
+    file: String,
+    started: String,
+    duration_sec: u64,
+    frames: u64,
+    width: i32,
+    height: i32,
+    fps: f64,
+    #[serde(skip_serializing_if = "metadata_is_empty")]
+    metadata: &'a CameraMetadata,
+}
+
+#[cfg(feature = "video")]
+fn metadata_is_empty(metadata: &&CameraMetadata) -> bool {
+    metadata.is_empty()
+}
+
+#[cfg(feature = "video")]
+impl RecordingSidecar<'_> {
+    fn write(&self, video_path: &Path) -> Result<PathBuf, String> {
+        let mut sidecar_path = video_path.as_os_str().to_owned();
+        sidecar_path.push(".json");
+        let sidecar_path = PathBuf::from(sidecar_path);
+
+        let file = std::fs::File::create(&sidecar_path).map_err(|e| e.to_string())?;
+        serde_json::to_writer_pretty(file, self).map_err(|e| e.to_string())?;
+        Ok(sidecar_path)
+    }
 }
 
 // ============================================================================
@@ -208,6 +1001,31 @@ pub enum VideoMessage {
     RecordingStopped { camera: String, duration_sec: u64 },
     Error(String),
     FramesCaptured(u64),
+    /// Downscaled JPEG of the current frame for live monitoring
+    PreviewFrame { camera: String, jpeg: Vec<u8> },
+    /// Motion started a new recorded stretch (motion recording mode)
+    MotionDetected { camera: String },
+}
+
+/// Decode a `PreviewFrame` JPEG into `[width, height]` and tightly packed RGB
+/// bytes, ready for a GUI texture
+#[cfg(feature = "video")]
+pub fn decode_preview(jpeg: &[u8]) -> Result<([usize; 2], Vec<u8>), String> {
+    let buf = Vector::<u8>::from_slice(jpeg);
+    let bgr = imgcodecs::imdecode(&buf, imgcodecs::IMREAD_COLOR).map_err(|e| e.to_string())?;
+    if bgr.empty() {
+        return Err("Preview frame is not a valid JPEG".to_string());
+    }
+    let mut rgb = Mat::default();
+    imgproc::cvt_color(&bgr, &mut rgb, imgproc::COLOR_BGR2RGB, 0).map_err(|e| e.to_string())?;
+    let size = [rgb.cols() as usize, rgb.rows() as usize];
+    let bytes = rgb.data_bytes().map_err(|e| e.to_string())?.to_vec();
+    Ok((size, bytes))
+}
+
+#[cfg(not(feature = "video"))]
+pub fn decode_preview(_jpeg: &[u8]) -> Result<([usize; 2], Vec<u8>), String> {
+    Err("Video recording feature not enabled. Build with --features video".to_string())
 }
 
 // ============================================================================
@@ -217,22 +1035,159 @@ pub enum VideoMessage {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum RecordingState {
     Idle,
+    /// Camera open and reading, frames discarded until `trigger_record`
+    Armed,
     Recording,
+    /// Camera still read (keeps streams alive) but frames are not written
+    Paused,
     Stopping,
     Error,
 }
 
-// ============================================================================
-// VIDEO RECORDER
-// ============================================================================
+/// Paces the capture loop to the source's frame interval. Live sources block
+/// in `read` until the next frame and are never delayed further; video files
+/// and fast-returning backends are held to the source fps instead of spinning.
+#[cfg(feature = "video")]
+struct FrameClock {
+    interval: Duration,
+    read_started: Instant,
+    read_took: Duration,
+}
 
-pub struct VideoRecorder {
-    camera_info: CameraInfo,
-    config: VideoConfig,
-    state: Arc<Mutex<RecordingState>>,
-    tx_to_gui: Option<Sender<VideoMessage>>,
+#[cfg(feature = "video")]
+impl FrameClock {
+    fn new(source_fps: f64) -> Self {
+        let fps = if source_fps.is_finite() && source_fps > 0.0 { source_fps } else { DEFAULT_FPS };
+        Self {
+            interval: Duration::from_secs_f64(1.0 / fps),
+            read_started: Instant::now(),
+            read_took: Duration::ZERO,
+        }
+    }
+
+    /// Run `read`, remembering how long it blocked
+    fn read<T>(&mut self, read: impl FnOnce() -> T) -> T {
+        self.read_started = Instant::now();
+        let result = read();
+        self.read_took = self.read_started.elapsed();
+        result
+    }
+
+    /// Sleep out the rest of the frame interval, but only when the last read
+    /// returned in under half of it; a blocking read already paced the loop
+    fn wait(&self) {
+        if self.read_took >= self.interval / 2 {
+            return;
+        }
+        if let Some(remaining) = self.interval.checked_sub(self.read_started.elapsed()) {
+            thread::sleep(remaining);
+        }
+    }
+}
+
+/// Decides which frames are recorded when the recording fps is below the
+/// source's, so the file plays back at the right speed
+#[cfg(feature = "video")]
+struct FramePacer {
+    /// None = record every frame
+    interval: Option<Duration>,
+    next_due: Instant,
+}
+
+#[cfg(feature = "video")]
+impl FramePacer {
+    fn new(record_fps: f64, source_fps: f64) -> Self {
+        Self {
+            interval: (record_fps > 0.0 && record_fps < source_fps)
+                .then(|| Duration::from_secs_f64(1.0 / record_fps)),
+            next_due: Instant::now(),
+        }
+    }
+
+    /// True if the frame read now should be kept
+    fn due(&mut self) -> bool {
+        let Some(interval) = self.interval else {
+            return true;
+        };
+        let now = Instant::now();
+        if now < self.next_due {
+            return false;
+        }
+        // Keep the cadence, but don't burst to catch up after a stall
+        self.next_due += interval;
+        if self.next_due < now {
+            self.next_due = now + interval;
+        }
+        true
+    }
+}
+
+/// Why a segment's capture loop ended
+#[cfg(feature = "video")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SegmentEnd {
+    /// Stop requested (stop_recording or shared flag)
+    Stopped,
+    /// Duration limit hit; may roll over to a new segment
+    Limit,
+    /// Unrecoverable read/write error
+    Failed,
+    /// A video file source ran out of frames
+    EndOfFile,
+}
+
+// ============================================================================
+// VIDEO RECORDER
+// ============================================================================
+
+pub struct VideoRecorder {
+    camera_info: CameraInfo,
+    config: VideoConfig,
+    state: Arc<Mutex<RecordingState>>,
+    tx_to_gui: Option<Sender<VideoMessage>>,
     thread_handle: Option<JoinHandle<()>>,
     stop_tx: Option<Sender<()>>,
+    stop_flag: Option<Arc<AtomicBool>>,
+    /// When the camera last delivered a frame (snapshots, first-frame wait)
+    last_frame: Arc<Mutex<Option<Instant>>>,
+    /// When a frame was last written to the file (stall detection)
+    last_written: Arc<Mutex<Option<Instant>>>,
+    /// Set when an armed recorder should start writing
+    triggered: Arc<AtomicBool>,
+    snapshot_tx: Option<Sender<SnapshotRequest>>,
+    stats: Arc<Mutex<RecordingStats>>,
+}
+
+/// Live counters for a recording session, see `VideoRecorder::stats`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RecordingStats {
+    /// Frames written across all segments of this session
+    pub frames_written: u64,
+    pub elapsed_sec: u64,
+    /// Frames written per second, measured over roughly the last second
+    /// (0 while motion recording is idle or reads are failing)
+    pub current_fps: f64,
+    /// Reads that returned no frame or an empty frame
+    pub dropped_frames: u64,
+    /// File of the current segment
+    pub output_path: Option<PathBuf>,
+}
+
+/// Path to write the latest frame to, and where to send the result
+type SnapshotRequest = (PathBuf, Sender<Result<(), String>>);
+
+/// State shared between a `VideoRecorder` and its recording thread
+#[cfg(feature = "video")]
+struct ThreadContext {
+    state: Arc<Mutex<RecordingState>>,
+    tx_gui: Option<Sender<VideoMessage>>,
+    stop_rx: Receiver<()>,
+    stop_flag: Option<Arc<AtomicBool>>,
+    last_frame: Arc<Mutex<Option<Instant>>>,
+    last_written: Arc<Mutex<Option<Instant>>>,
+    triggered: Arc<AtomicBool>,
+    snapshot_rx: Receiver<SnapshotRequest>,
+    stats: Arc<Mutex<RecordingStats>>,
 }
 
 impl VideoRecorder {
@@ -250,65 +1205,356 @@ impl VideoRecorder {
             tx_to_gui: None,
             thread_handle: None,
             stop_tx: None,
+            stop_flag: None,
+            last_frame: Arc::new(Mutex::new(None)),
+            last_written: Arc::new(Mutex::new(None)),
+            triggered: Arc::new(AtomicBool::new(false)),
+            snapshot_tx: None,
+            stats: Arc::new(Mutex::new(RecordingStats::default())),
         }
     }
 
+    /// Probe webcam indices 0..=9 and describe each one that opens, with its
+    /// reported resolution and fps (defaults are kept for unreported values)
+    #[cfg(feature = "video")]
+    pub fn enumerate_webcams() -> Vec<CameraInfo> {
+        let mut found = Vec::new();
+
+        for idx in 0..=MAX_WEBCAM_INDEX {
+            let Ok(mut camera) = VideoCapture::new(idx, CAP_ANY) else {
+                continue;
+            };
+            if !camera.is_opened().unwrap_or(false) {
+                continue;
+            }
+
+            let prop = |camera: &VideoCapture, id| camera.get(id).ok().filter(|v| v.is_finite() && *v > 0.0);
+            let width = prop(&camera, videoio::CAP_PROP_FRAME_WIDTH).map_or(DEFAULT_WIDTH, |v| v as i32);
+            let height = prop(&camera, videoio::CAP_PROP_FRAME_HEIGHT).map_or(DEFAULT_HEIGHT, |v| v as i32);
+            let fps = prop(&camera, videoio::CAP_PROP_FPS).unwrap_or(DEFAULT_FPS);
+            let _ = camera.release();
+
+            info!("Found webcam {}: {}x{} @ {:.1} fps", idx, width, height, fps);
+            found.push(
+                CameraInfo::new(format!("Webcam {}", idx), CameraSource::Webcam(idx))
+                    .with_resolution(width, height)
+                    .with_fps(fps),
+            );
+        }
+
+        found
+    }
+
     /// Set GUI message sender
     pub fn with_gui_sender(mut self, tx: Sender<VideoMessage>) -> Self {
         self.tx_to_gui = Some(tx);
         self
     }
 
+    /// Share an external stop flag (e.g. the automation's) so one flag can
+    /// halt automation and recording together. Checked alongside `stop_recording`.
+    pub fn with_stop_flag(mut self, flag: Arc<AtomicBool>) -> Self {
+        self.stop_flag = Some(flag);
+        self
+    }
+
+    /// Name of the camera this recorder captures
+    pub fn camera_name(&self) -> &str {
+        &self.camera_info.name
+    }
+
     /// Check if currently recording
     pub fn is_recording(&self) -> bool {
         *self.state.lock().unwrap() == RecordingState::Recording
     }
 
+    /// Seconds since a frame was last written, or None if not recording or no
+    /// frame has been written yet. A growing value while recording means a stall.
+    pub fn seconds_since_last_frame(&self) -> Option<u64> {
+        if !self.is_recording() {
+            return None;
+        }
+        self.last_written.lock().unwrap().map(|t| t.elapsed().as_secs())
+    }
+
+    /// Snapshot of the live counters of the current (or last) session
+    pub fn stats(&self) -> RecordingStats {
+        self.stats.lock().unwrap().clone()
+    }
+
     /// Get current state
     pub fn get_state(&self) -> RecordingState {
         *self.state.lock().unwrap()
     }
 
+    /// Check if the camera is open: recording, paused or armed
+    pub fn is_active(&self) -> bool {
+        matches!(
+            *self.state.lock().unwrap(),
+            RecordingState::Recording | RecordingState::Paused | RecordingState::Armed
+        )
+    }
+
+    /// Check if armed and waiting for a trigger
+    pub fn is_armed(&self) -> bool {
+        *self.state.lock().unwrap() == RecordingState::Armed
+    }
+
     /// Start recording
     pub fn start_recording(&mut self) -> Result<(), String> {
+        self.spawn_thread(false)
+    }
+
+    /// Open the camera and keep reading (discarding) frames without writing,
+    /// so `trigger_record` can start a recording with no connection delay
+    pub fn arm(&mut self) -> Result<(), String> {
+        self.spawn_thread(true)
+    }
+
+    /// Start writing from the armed state
+    pub fn trigger_record(&mut self) -> Result<(), String> {
+        let mut state = self.state.lock().unwrap();
+        if *state != RecordingState::Armed {
+            return Err("Recorder is not armed".to_string());
+        }
+
+        info!("Recording triggered for camera: {}", self.camera_info.name);
+        self.triggered.store(true, Ordering::SeqCst);
+        *state = RecordingState::Recording;
+        Ok(())
+    }
+
+    /// Save the most recent frame as a JPEG to `path` (which must end in `.jpg`
+    /// or `.jpeg`) without interrupting the recording. Works while recording,
+    /// paused or armed; waits up to 5s for the next frame.
+    pub fn capture_snapshot(&self, path: &Path) -> Result<(), String> {
+        let is_jpeg = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| e.eq_ignore_ascii_case("jpg") || e.eq_ignore_ascii_case("jpeg"));
+        if !is_jpeg {
+            return Err(format!("Snapshot path must end in .jpg or .jpeg: {}", path.display()));
+        }
+        if !self.is_recording() && !self.is_armed() && !self.is_paused() {
+            return Err("Recorder is idle".to_string());
+        }
+        let requests = self.snapshot_tx.as_ref().ok_or("Recorder is idle")?;
+
+        let (reply_tx, reply_rx) = channel();
+        requests
+            .send((path.to_path_buf(), reply_tx))
+            .map_err(|_| "Recording thread has exited".to_string())?;
+
+        reply_rx
+            .recv_timeout(SNAPSHOT_TIMEOUT)
+            .map_err(|_| format!("No frame within {}s", SNAPSHOT_TIMEOUT.as_secs()))??;
+
+        let age = self.last_frame.lock().unwrap().map(|t| t.elapsed());
+        info!(
+            "Snapshot of {} saved to {} (frame age {})",
+            self.camera_info.name,
+            path.display(),
+            age.map_or("unknown".to_string(), |d| format!("{}ms", d.as_millis()))
+        );
+        Ok(())
+    }
+
+    /// Check if recording is paused
+    pub fn is_paused(&self) -> bool {
+        *self.state.lock().unwrap() == RecordingState::Paused
+    }
+
+    /// Stop writing frames while keeping the camera and the current file open
+    pub fn pause_recording(&mut self) -> Result<(), String> {
+        {
+            let mut state = self.state.lock().unwrap();
+            match *state {
+                RecordingState::Recording => *state = RecordingState::Paused,
+                RecordingState::Paused => return Err("Recording is already paused".to_string()),
+                _ => return Err("Cannot pause: not currently recording".to_string()),
+            }
+        }
+
+        info!("Recording paused for camera: {}", self.camera_info.name);
+        self.send_message(VideoMessage::Status("Paused".to_string()));
+        Ok(())
+    }
+
+    /// Resume writing into the same file after `pause_recording`
+    pub fn resume_recording(&mut self) -> Result<(), String> {
+        {
+            let mut state = self.state.lock().unwrap();
+            if *state != RecordingState::Paused {
+                return Err("Cannot resume: recording is not paused".to_string());
+            }
+            *state = RecordingState::Recording;
+        }
+
+        info!("Recording resumed for camera: {}", self.camera_info.name);
+        self.send_message(VideoMessage::Status("Recording".to_string()));
+        Ok(())
+    }
+
+    /// Close the camera of an armed recorder (or stop a triggered recording)
+    pub fn disarm(&mut self) -> Result<(), String> {
+        self.stop_recording()
+    }
+
+    /// Handle an event in one call: save `pre_burst` snapshots of consecutive
+    /// frames to `<output_dir>/events/<date>/`, record for `record_secs`, then
+    /// stop. An idle recorder is armed first; an armed one is armed again
+    /// afterwards. Blocks until the recording has stopped (or the stop flag is
+    /// set) and returns the snapshot paths. A recorder armed here is disarmed
+    /// again if the event fails.
+    pub fn capture_event(&mut self, pre_burst: usize, record_secs: u64) -> Result<Vec<PathBuf>, String> {
+        let was_armed = match self.get_state() {
+            RecordingState::Armed => true,
+            RecordingState::Idle | RecordingState::Error => {
+                self.arm()?;
+                false
+            }
+            RecordingState::Recording => return Err("Cannot capture event: already recording".to_string()),
+            RecordingState::Paused => return Err("Cannot capture event: recording is paused".to_string()),
+            RecordingState::Stopping => return Err("Cannot capture event: recorder is still stopping".to_string()),
+        };
+
+        let result = self.record_event(pre_burst, record_secs, was_armed);
+        if result.is_err() && !was_armed && (self.is_armed() || self.is_recording() || self.is_paused()) {
+            if let Err(e) = self.disarm() {
+                warn!("Failed to disarm {} after event error: {}", self.camera_info.name, e);
+            }
+        }
+        result
+    }
+
+    /// Body of `capture_event` once the recorder is armed
+    fn record_event(&mut self, pre_burst: usize, record_secs: u64, was_armed: bool) -> Result<Vec<PathBuf>, String> {
+        if !was_armed {
+            self.wait_for_first_frame()?;
+        }
+
+        info!("Event on {}: {} snapshot(s) then {}s recording", self.camera_info.name, pre_burst, record_secs);
+        self.send_message(VideoMessage::Log(format!(
+            "Event: {} ({} snapshots, {}s clip)",
+            self.camera_info.name, pre_burst, record_secs
+        )));
+
+        let now = Local::now();
+        let dir = self.config.output_dir.join("events").join(now.format("%Y-%m-%d").to_string());
+        std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+        let stamp = now.format("%Y%m%d_%H%M%S");
+        let safe_name = sanitize_filename(&self.camera_info.name);
+        let mut snapshots = Vec::with_capacity(pre_burst);
+        for n in 1..=pre_burst {
+            let path = dir.join(format!("{}_{}_{}.jpg", safe_name, stamp, n));
+            // Each request is answered with the next frame, so the burst is consecutive frames
+            match self.capture_snapshot(&path) {
+                Ok(()) => snapshots.push(path),
+                Err(e) => warn!("Event snapshot {}/{} failed: {}", n, pre_burst, e),
+            }
+        }
+
+        self.trigger_record()?;
+
+        let deadline = Instant::now() + Duration::from_secs(record_secs);
+        while Instant::now() < deadline && self.is_recording() {
+            if self.stop_flag.as_ref().is_some_and(|f| f.load(Ordering::SeqCst)) {
+                break;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+
+        if self.is_recording() || self.is_paused() {
+            self.stop_recording()?;
+        }
+        let stopped = self.stop_flag.as_ref().is_some_and(|f| f.load(Ordering::SeqCst));
+        if was_armed && !stopped {
+            self.arm()?;
+        }
+        Ok(snapshots)
+    }
+
+    /// After `arm`, wait until the camera delivers a frame (or the thread gives up)
+    fn wait_for_first_frame(&self) -> Result<(), String> {
+        let timeout = Duration::from_secs(
+            self.config.first_frame_timeout_sec.unwrap_or(DEFAULT_FIRST_FRAME_TIMEOUT_SEC),
+        );
+        let started = Instant::now();
+        while self.last_frame.lock().unwrap().is_none() {
+            if !self.is_armed() {
+                return Err(format!("{} failed to arm", self.camera_info.name));
+            }
+            if started.elapsed() >= timeout {
+                return Err(format!("No frame from {} within {}s", self.camera_info.name, timeout.as_secs()));
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+        Ok(())
+    }
+
+    /// Spawn the recording thread, either writing immediately or armed
+    fn spawn_thread(&mut self, armed: bool) -> Result<(), String> {
         // Check if already recording
         {
             let state = self.state.lock().unwrap();
-            if *state == RecordingState::Recording {
-                return Err("Already recording".to_string());
+            match *state {
+                RecordingState::Recording => return Err("Already recording".to_string()),
+                RecordingState::Armed => return Err("Already armed".to_string()),
+                RecordingState::Paused => return Err("Already recording (paused)".to_string()),
+                _ => {}
             }
         }
 
+        self.config.check_free_space()?;
+
         #[cfg(not(feature = "video"))]
         {
+            let _ = armed;
             return Err("Video recording feature not enabled. Build with --features video".to_string());
         }
 
         #[cfg(feature = "video")]
         {
-            info!("Starting recording for camera: {}", self.camera_info.name);
+            info!("{} camera: {}", if armed { "Arming" } else { "Starting recording for" }, self.camera_info.name);
             self.send_message(VideoMessage::Log(format!(
-                "Starting recording: {} ({})",
+                "{}: {} ({})",
+                if armed { "Arming" } else { "Starting recording" },
                 self.camera_info.name,
                 self.camera_info.source.source_type()
             )));
 
-            // Create stop channel
+            // Create stop and snapshot channels
             let (stop_tx, stop_rx) = channel();
             self.stop_tx = Some(stop_tx);
+            let (snapshot_tx, snapshot_rx) = channel();
+            self.snapshot_tx = Some(snapshot_tx);
 
             // Clone data for thread
             let camera_info = self.camera_info.clone();
             let config = self.config.clone();
-            let state = self.state.clone();
-            let tx_gui = self.tx_to_gui.clone();
+            *self.last_frame.lock().unwrap() = None;
+            *self.last_written.lock().unwrap() = None;
+            *self.stats.lock().unwrap() = RecordingStats::default();
+            self.triggered.store(!armed, Ordering::SeqCst);
+            let context = ThreadContext {
+                state: self.state.clone(),
+                tx_gui: self.tx_to_gui.clone(),
+                stop_rx,
+                stop_flag: self.stop_flag.clone(),
+                last_frame: self.last_frame.clone(),
+                last_written: self.last_written.clone(),
+                triggered: self.triggered.clone(),
+                snapshot_rx,
+                stats: self.stats.clone(),
+            };
 
             // Update state
-            *self.state.lock().unwrap() = RecordingState::Recording;
+            *self.state.lock().unwrap() = if armed { RecordingState::Armed } else { RecordingState::Recording };
 
             // Spawn recording thread
             let handle = thread::spawn(move || {
-                Self::recording_thread(camera_info, config, state, tx_gui, stop_rx);
+                Self::recording_thread(camera_info, config, context);
             });
 
             self.thread_handle = Some(handle);
@@ -317,11 +1563,11 @@ impl VideoRecorder {
         }
     }
 
-    /// Stop recording
+    /// Stop recording (also disarms an armed recorder)
     pub fn stop_recording(&mut self) -> Result<(), String> {
         let current_state = *self.state.lock().unwrap();
         
-        if current_state != RecordingState::Recording {
+        if !matches!(current_state, RecordingState::Recording | RecordingState::Armed | RecordingState::Paused) {
             return Err("Not currently recording".to_string());
         }
 
@@ -346,6 +1592,7 @@ impl VideoRecorder {
 
         *self.state.lock().unwrap() = RecordingState::Idle;
         self.stop_tx = None;
+        self.snapshot_tx = None;
 
         Ok(())
     }
@@ -357,22 +1604,30 @@ impl VideoRecorder {
         }
     }
 
-    /// Generate output filename
-    fn generate_filename(camera_name: &str, format: VideoFormat) -> String {
+    /// Generate output filename. Adds a `_2`, `_3`... suffix if a file with
+    /// the same timestamp already exists in `dir` (e.g. back-to-back segments).
+    fn generate_filename(dir: &Path, camera_name: &str, format: VideoFormat) -> String {
         let timestamp = Local::now().format("%Y%m%d_%H%M%S");
-        let safe_name = camera_name.replace(' ', "_").replace('/', "_");
-        format!("{}_{}.{}", safe_name, timestamp, format.extension())
+        let stem = format!("{}_{}", sanitize_filename(camera_name), timestamp);
+        Self::unique_filename(dir, &stem, format.extension())
+    }
+
+    /// `<stem>.<extension>`, or `<stem>_2.<extension>`, `_3`... if taken in `dir`
+    fn unique_filename(dir: &Path, stem: &str, extension: &str) -> String {
+        let mut filename = format!("{}.{}", stem, extension);
+        let mut n = 2;
+        while dir.join(&filename).exists() {
+            filename = format!("{}_{}.{}", stem, n, extension);
+            n += 1;
+        }
+        filename
     }
 
     /// Recording thread implementation
     #[cfg(feature = "video")]
-    fn recording_thread(
-        camera_info: CameraInfo,
-        config: VideoConfig,
-        state: Arc<Mutex<RecordingState>>,
-        tx_gui: Option<Sender<VideoMessage>>,
-        stop_rx: Receiver<()>,
-    ) {
+    fn recording_thread(camera_info: CameraInfo, config: VideoConfig, context: ThreadContext) {
+        let ThreadContext { state, tx_gui, stop_rx, stop_flag, last_frame, last_written, triggered, snapshot_rx, stats } = context;
+
         let send_msg = |msg: VideoMessage| {
             if let Some(tx) = &tx_gui {
                 let _ = tx.send(msg);
@@ -390,42 +1645,26 @@ impl VideoRecorder {
             *state.lock().unwrap() = RecordingState::Error;
         };
 
+        let stop_requested = || {
+            stop_rx.try_recv().is_ok() || stop_flag.as_ref().is_some_and(|f| f.load(Ordering::SeqCst))
+        };
+
         // Open camera
         send_log(format!("Opening camera source: {:?}", camera_info.source));
         
-        let mut camera = match camera_info.source {
-            CameraSource::Webcam(idx) => {
-                match VideoCapture::new(idx, CAP_ANY) {
-                    Ok(cam) => cam,
-                    Err(e) => {
-                        send_error(format!("Failed to open webcam {}: {}", idx, e));
-                        return;
-                    }
-                }
-            }
-            _ => {
-                match VideoCapture::from_file(&camera_info.source.to_opencv_string(), CAP_ANY) {
-                    Ok(cam) => cam,
-                    Err(e) => {
-                        send_error(format!("Failed to open camera source: {}", e));
-                        return;
-                    }
-                }
-            }
-        };
-
-        // Check if camera opened
-        match camera.is_opened() {
-            Ok(true) => send_log("Camera opened successfully".to_string()),
-            Ok(false) => {
-                send_error("Camera failed to open".to_string());
+        let mut camera = match Self::connect(&camera_info, &config, &stop_requested, &send_log) {
+            Ok(Some(cam)) => cam,
+            Ok(None) => {
+                send_log("Stop signal received while connecting".to_string());
+                *state.lock().unwrap() = RecordingState::Idle;
                 return;
             }
             Err(e) => {
-                send_error(format!("Error checking camera status: {}", e));
+                send_error(e);
                 return;
             }
-        }
+        };
+        send_log("Camera opened successfully".to_string());
 
         // Set camera properties
         let _ = camera.set(videoio::CAP_PROP_FRAME_WIDTH, camera_info.width as f64);
@@ -442,128 +1681,872 @@ impl VideoRecorder {
             actual_width, actual_height, actual_fps
         ));
 
-        // Generate output filename
-        let filename = Self::generate_filename(&camera_info.name, config.format);
-        let output_path = config.output_dir.join(&filename);
-
-        send_log(format!("Output file: {}", output_path.display()));
+        // Resolve the output format
+        let format = if config.format == VideoFormat::Auto {
+            let source_codec = camera.get(videoio::CAP_PROP_FOURCC)
+                .map(|code| fourcc_to_string(code as i32))
+                .unwrap_or_default();
+            let (format, reason) = VideoFormat::auto_select(&camera_info.source, &source_codec);
+            send_log(format!("Auto-selected {:?} format: {}", format, reason));
+            format
+        } else {
+            config.format
+        };
 
-        // Create video writer
-        let fourcc = config.format.fourcc();
         let frame_size = Size::new(actual_width, actual_height);
-        
-        let mut writer = match VideoWriter::new(
-            output_path.to_str().unwrap(),
-            fourcc,
-            actual_fps,
-            frame_size,
-            true,
-        ) {
-            Ok(w) => w,
-            Err(e) => {
-                send_error(format!("Failed to create video writer: {}", e));
-                return;
-            }
-        };
+        let mut frame = Mat::default();
 
-        // Check if writer opened
-        match writer.is_opened() {
-            Ok(true) => send_log("Video writer ready".to_string()),
-            Ok(false) => {
-                send_error("Video writer failed to open".to_string());
-                return;
-            }
-            Err(e) => {
-                send_error(format!("Error checking writer status: {}", e));
-                return;
-            }
+        // Record at the configured fps when the source delivers more (e.g. a
+        // 5 fps archive of a 30 fps camera), dropping the extra frames
+        let source_fps = if actual_fps > 0.0 { actual_fps } else { camera_info.fps };
+        let record_fps = if camera_info.fps > 0.0 { camera_info.fps.min(source_fps) } else { source_fps };
+        let mut pacer = FramePacer::new(record_fps, source_fps);
+        let mut clock = FrameClock::new(source_fps);
+        if record_fps < source_fps {
+            send_log(format!("Capping recording at {:.1} fps (source delivers {:.1} fps)", record_fps, source_fps));
         }
 
-        // Notify recording started
-        send_msg(VideoMessage::RecordingStarted {
-            camera: camera_info.name.clone(),
-            filename: filename.clone(),
+        // Pre-record buffer, oldest frame first; dropped with the thread on stop
+        let prebuffer_cap = config.prebuffer_sec.map(|sec| {
+            ((sec as f64 * record_fps).ceil() as usize).clamp(1, MAX_PREBUFFER_FRAMES)
         });
+        let mut prebuffer: VecDeque<Mat> = VecDeque::new();
+
+        let preview_interval = config.preview_fps.map(|fps| Duration::from_secs_f64(1.0 / fps));
+        let mut last_preview: Option<Instant> = None;
+
+        // Armed: keep the stream flowing (discarding frames) until triggered
+        if !triggered.load(Ordering::SeqCst) {
+            send_log("Armed: camera open, waiting for trigger".to_string());
+            while !triggered.load(Ordering::SeqCst) {
+                if stop_requested() {
+                    send_log("Disarmed".to_string());
+                    let _ = camera.release();
+                    *state.lock().unwrap() = RecordingState::Idle;
+                    return;
+                }
 
-        // Recording loop
-        let start_time = Instant::now();
-        let mut frame_count: u64 = 0;
-        let mut frame = Mat::default();
-
-        send_log("Recording started".to_string());
+                match clock.read(|| camera.read(&mut frame)) {
+                    Ok(true) => {
+                        *last_frame.lock().unwrap() = Some(Instant::now());
+                        Self::serve_snapshots(&snapshot_rx, &frame);
+                        if let Some(interval) = preview_interval {
+                            if last_preview.is_none_or(|t| t.elapsed() >= interval) {
+                                last_preview = Some(Instant::now());
+                                let overlay = config.overlay_target.preview()
+                                    .then(|| config.overlay_text(&camera_info.name, "ARMED"))
+                                    .flatten();
+                                match Self::encode_preview(&frame, config.preview_width, overlay.as_deref(), &config) {
+                                    Ok(jpeg) => send_msg(VideoMessage::PreviewFrame {
+                                        camera: camera_info.name.clone(),
+                                        jpeg,
+                                    }),
+                                    Err(e) => warn!("Failed to encode preview frame: {}", e),
+                                }
+                            }
+                        }
+                        if let Some(cap) = prebuffer_cap.filter(|_| pacer.due()) {
+                            Self::buffer_frame(&mut prebuffer, cap, &frame, &camera_info.name, &config);
+                        }
+                        clock.wait();
+                    }
+                    Ok(false) => thread::sleep(Duration::from_millis(100)),
+                    Err(e) => {
+                        send_error(format!("Error reading frame while armed: {}", e));
+                        let _ = camera.release();
+                        return;
+                    }
+                }
+            }
+            send_log("Trigger received, recording".to_string());
+        }
 
+        // "Connected but dead" detection
+        let opened_at = Instant::now();
+        let mut got_first_frame = false;
+
+        let mut motion = (config.snapshot_on_motion || config.motion_recording)
+            .then(|| MotionDetector::new(config.motion_threshold));
+        let mut last_snapshot: Option<Instant> = None;
+        let mut last_motion: Option<Instant> = None;
+        let mut motion_active = false;
+
+        // One iteration per segment; the camera stays open across segments
+        let mut segment: u32 = 0;
+        let mut restart_rng = config.restart_seed.map_or_else(SimpleRng::from_clock, SimpleRng::new);
+        let session_start = Instant::now();
+        let mut fps_window = (Instant::now(), 0u64);
         loop {
-            // Check for stop signal
-            if stop_rx.try_recv().is_ok() {
-                send_log("Stop signal received".to_string());
+            if let Some(hours) = config.segment_retention_hours {
+                let removed = Self::sweep_old_segments(&config.output_dir, config.subdir_depth(), &camera_info.name, hours);
+                if removed > 0 {
+                    send_log(format!("Retention: removed {} segment(s) older than {}h", removed, hours));
+                }
+            }
+
+            // Generate output filename
+            segment += 1;
+            let segment_dir = config.segment_dir(&camera_info.name, Local::now());
+            if let Err(e) = std::fs::create_dir_all(&segment_dir) {
+                send_error(format!("Failed to create {}: {}", segment_dir.display(), e));
                 break;
             }
+            let filename = Self::generate_filename(&segment_dir, &camera_info.name, format);
+
+            if segment > 1 {
+                send_log(format!("Rolling over to segment {}", segment));
+            }
 
-            // Check duration limit
-            if let Some(max_dur) = config.max_duration_sec {
-                if start_time.elapsed().as_secs() >= max_dur {
-                    send_log(format!("Max duration reached: {}s", max_dur));
+            // Create video writer, falling back to a smaller size or MJPG if needed
+            let fourcc = config.fourcc.map(i32::from_le_bytes).unwrap_or_else(|| format.fourcc());
+            let (mut writer, output_path, fourcc, write_size) = match Self::open_writer_with_fallback(
+                &segment_dir.join(&filename),
+                fourcc,
+                frame_size,
+                record_fps,
+                &send_log,
+            ) {
+                Ok(opened) => opened,
+                Err(e) => {
+                    send_error(e);
                     break;
                 }
+            };
+            let filename = output_path.file_name().map_or(filename, |n| n.to_string_lossy().into_owned());
+            stats.lock().unwrap().output_path = Some(output_path.clone());
+            send_log(format!("Output file: {}", output_path.display()));
+            // The backend may substitute another codec; report what it actually uses
+            let negotiated = writer.get(videoio::CAP_PROP_FOURCC).ok().map(|v| v as i32).filter(|&c| c != 0);
+            match negotiated {
+                Some(code) if code != fourcc => send_log(format!(
+                    "Video writer ready (requested FourCC {}, backend negotiated {})",
+                    fourcc_to_string(fourcc),
+                    fourcc_to_string(code)
+                )),
+                _ => send_log(format!("Video writer ready (FourCC {})", fourcc_to_string(fourcc))),
             }
 
-            // Read frame
-            match camera.read(&mut frame) {
-                Ok(true) => {
-                    if frame.empty() {
-                        warn!("Empty frame received");
-                        continue;
-                    }
+            // Notify recording started
+            send_msg(VideoMessage::RecordingStarted {
+                camera: camera_info.name.clone(),
+                filename: filename.clone(),
+            });
 
-                    // Write frame
-                    if let Err(e) = writer.write(&frame) {
-                        send_error(format!("Failed to write frame: {}", e));
-                        break;
+            // Recording loop
+            let start_time = Instant::now();
+            let started_at = Local::now();
+            let mut frame_count: u64 = 0;
+            let mut failed_reads: u32 = 0;
+            let mut empty_frames: u32 = 0;
+            let mut last_read_warning: Option<Instant> = None;
+
+            send_log("Recording started".to_string());
+
+            let end = loop {
+                // Refresh every iteration so fps drops to 0 while nothing is written
+                {
+                    let mut stats = stats.lock().unwrap();
+                    stats.elapsed_sec = session_start.elapsed().as_secs();
+                    let window = fps_window.0.elapsed();
+                    if window >= Duration::from_secs(1) {
+                        stats.current_fps = fps_window.1 as f64 / window.as_secs_f64();
+                        fps_window = (Instant::now(), 0);
                     }
+                }
 
-                    frame_count += 1;
+                // Check for stop signal
+                if stop_rx.try_recv().is_ok() {
+                    send_log("Stop signal received".to_string());
+                    break SegmentEnd::Stopped;
+                }
+
+                if stop_flag.as_ref().is_some_and(|f| f.load(Ordering::SeqCst)) {
+                    send_log("Shared stop flag set".to_string());
+                    break SegmentEnd::Stopped;
+                }
 
-                    // Send progress update every 100 frames
-                    if frame_count % 100 == 0 {
-                        send_msg(VideoMessage::FramesCaptured(frame_count));
+                // Check duration limit
+                if let Some(max_dur) = config.max_duration_sec {
+                    if start_time.elapsed().as_secs() >= max_dur {
+                        send_log(format!("Max duration reached: {}s", max_dur));
+                        break SegmentEnd::Limit;
                     }
                 }
-                Ok(false) => {
-                    warn!("Failed to read frame from camera");
-                    thread::sleep(Duration::from_millis(100));
+
+                if let Some(timeout) = config.first_frame_timeout_sec {
+                    if !got_first_frame && opened_at.elapsed().as_secs() >= timeout {
+                        send_error(format!("No frames received within {}s of opening the camera", timeout));
+                        break SegmentEnd::Failed;
+                    }
                 }
-                Err(e) => {
-                    send_error(format!("Error reading frame: {}", e));
+
+                // Read frame; an empty frame counts as a failed read
+                let read = clock.read(|| camera.read(&mut frame));
+                let empty = matches!(read, Ok(true)) && frame.empty();
+                match read {
+                    Ok(true) if !empty => {
+                        got_first_frame = true;
+                        failed_reads = 0;
+                        empty_frames = 0;
+
+                        *last_frame.lock().unwrap() = Some(Instant::now());
+
+                        let detected = match motion.as_mut().map(|d| d.update(&frame)) {
+                            Some(Ok(result)) => result,
+                            Some(Err(e)) => {
+                                warn!("Motion detection failed: {}", e);
+                                None
+                            }
+                            None => None,
+                        };
+
+                        // Motion recording: write only during motion plus the cooldown
+                        let write = if config.motion_recording {
+                            if detected.is_some() {
+                                last_motion = Some(Instant::now());
+                            }
+                            let cooldown = Duration::from_secs(config.motion_cooldown_sec);
+                            let active = last_motion.is_some_and(|t| t.elapsed() <= cooldown);
+                            if active && !motion_active {
+                                send_log(format!("Motion detected on {}, recording", camera_info.name));
+                                send_msg(VideoMessage::MotionDetected {
+                                    camera: camera_info.name.clone(),
+                                });
+                            } else if !active && motion_active {
+                                send_log(format!("Motion ended on {}, pausing", camera_info.name));
+                            }
+                            motion_active = active;
+                            active
+                        } else {
+                            true
+                        };
+
+                        let paused = *state.lock().unwrap() == RecordingState::Paused;
+                        let write = write && !paused;
+                        let indicator = if write { "REC" } else if paused { "PAUSED" } else { "STANDBY" };
+                        // Frames dropped by the fps cap are neither buffered nor written
+                        let due = pacer.due();
+
+                        // Send preview frame at the configured rate (before the recording overlay)
+                        if let Some(interval) = preview_interval {
+                            if last_preview.is_none_or(|t| t.elapsed() >= interval) {
+                                last_preview = Some(Instant::now());
+                                let overlay = config.overlay_target.preview()
+                                    .then(|| config.overlay_text(&camera_info.name, indicator))
+                                    .flatten();
+                                match Self::encode_preview(&frame, config.preview_width, overlay.as_deref(), &config) {
+                                    Ok(jpeg) => send_msg(VideoMessage::PreviewFrame {
+                                        camera: camera_info.name.clone(),
+                                        jpeg,
+                                    }),
+                                    Err(e) => warn!("Failed to encode preview frame: {}", e),
+                                }
+                            }
+                        }
+
+                        // Keep lead-up frames while waiting for motion (not while paused)
+                        if !write && !paused && due {
+                            if let Some(cap) = prebuffer_cap {
+                                Self::buffer_frame(&mut prebuffer, cap, &frame, &camera_info.name, &config);
+                            }
+                        }
+
+                        // Write frame (unless paused), preceded by any buffered lead-up
+                        if write && due {
+                            if !prebuffer.is_empty() {
+                                let buffered = prebuffer.len() as u64;
+                                for old in prebuffer.drain(..) {
+                                    if let Err(e) = Self::write_frame(&mut writer, &old, write_size) {
+                                        warn!("Failed to write pre-record frame: {}", e);
+                                    }
+                                }
+                                frame_count += buffered;
+                                stats.lock().unwrap().frames_written += buffered;
+                                send_log(format!("Wrote {} pre-record frame(s)", buffered));
+                            }
+                            if config.overlay_target.recording() {
+                                if let Some(text) = config.overlay_text(&camera_info.name, indicator) {
+                                    if let Err(e) = Self::draw_overlay(&mut frame, &text, &config) {
+                                        warn!("Failed to draw overlay: {}", e);
+                                    }
+                                }
+                            }
+                            if let Err(e) = Self::write_frame(&mut writer, &frame, write_size) {
+                                send_error(format!("Failed to write frame: {}", e));
+                                break SegmentEnd::Failed;
+                            }
+                            *last_written.lock().unwrap() = Some(Instant::now());
+                            frame_count += 1;
+                            fps_window.1 += 1;
+                            stats.lock().unwrap().frames_written += 1;
+                        }
+
+                        Self::serve_snapshots(&snapshot_rx, &frame);
+
+                        // Save a snapshot on motion (rate limited)
+                        if config.snapshot_on_motion {
+                            if let Some(result) = detected {
+                                let interval = Duration::from_secs(config.snapshot_interval_sec);
+                                if last_snapshot.is_none_or(|t| t.elapsed() >= interval) {
+                                    last_snapshot = Some(Instant::now());
+                                    match Self::save_motion_snapshot(&frame, &camera_info.name, &config.output_dir, &result) {
+                                        Ok(path) => send_log(format!(
+                                            "Motion snapshot (score {:.1}): {}",
+                                            result.score,
+                                            path.display()
+                                        )),
+                                        Err(e) => warn!("Failed to save motion snapshot: {}", e),
+                                    }
+                                }
+                            }
+                        }
+
+                        if write && due && frame_count % DISK_CHECK_INTERVAL_FRAMES == 0 {
+                            if let Err(e) = config.check_free_space() {
+                                send_error(format!("{}, stopping recording", e));
+                                break SegmentEnd::Failed;
+                            }
+                        }
+
+                        // Send progress update (and check file size) every 100 frames
+                        if write && due && frame_count % 100 == 0 {
+                            send_msg(VideoMessage::FramesCaptured(frame_count));
+
+                            if let Some(max_mb) = config.max_file_size_mb {
+                                let size = std::fs::metadata(&output_path).map(|m| m.len()).unwrap_or(0);
+                                if size >= max_mb.saturating_mul(1_048_576) {
+                                    send_log(format!(
+                                        "Max file size reached: {} MB ({} bytes)",
+                                        max_mb, size
+                                    ));
+                                    break SegmentEnd::Limit;
+                                }
+                            }
+                        }
+                    }
+                    Ok(_) => {
+                        failed_reads += 1;
+
+                        // Files end; only live sources are worth waiting for or reconnecting
+                        if matches!(camera_info.source, CameraSource::VideoFile(_)) {
+                            if failed_reads >= VIDEO_FILE_EOF_READS {
+                                send_log("End of video file reached".to_string());
+                                break SegmentEnd::EndOfFile;
+                            }
+                            continue;
+                        }
+
+                        if last_read_warning.is_none_or(|t| t.elapsed() >= READ_WARNING_INTERVAL) {
+                            last_read_warning = Some(Instant::now());
+                            let what = if empty { "Empty frame received" } else { "Failed to read frame from camera" };
+                            warn!("{} ({} consecutive)", what, failed_reads);
+                        }
+                        stats.lock().unwrap().dropped_frames += 1;
+
+                        if empty {
+                            empty_frames += 1;
+                            if config.max_empty_frames > 0
+                                && empty_frames >= config.max_empty_frames
+                                && !camera_info.source.is_network_stream()
+                            {
+                                send_error(format!("Camera delivered {} empty frames in a row, stopping", empty_frames));
+                                break SegmentEnd::Failed;
+                            }
+                        }
+
+                        let threshold = config.reconnect_after_failed_reads;
+                        if threshold > 0 && failed_reads >= threshold && camera_info.source.is_network_stream() {
+                            let _ = camera.release();
+                            match Self::reconnect(&camera_info, &config, &stop_requested, &send_log) {
+                                Ok(Some(cam)) => {
+                                    camera = cam;
+                                    failed_reads = 0;
+                                    send_log(format!("Reconnected, resuming {}", filename));
+                                }
+                                Ok(None) => {
+                                    send_log("Stop signal received while reconnecting".to_string());
+                                    break SegmentEnd::Stopped;
+                                }
+                                Err(e) => {
+                                    send_error(e);
+                                    break SegmentEnd::Failed;
+                                }
+                            }
+                            continue;
+                        }
+
+                        thread::sleep(Duration::from_millis(if empty { 10 } else { 100 }));
+                    }
+                    Err(e) => {
+                        send_error(format!("Error reading frame: {}", e));
+                        break SegmentEnd::Failed;
+                    }
+                }
+
+                // Wait out the rest of the frame interval instead of spinning
+                clock.wait();
+            };
+
+            // Finalize this segment
+            let duration = start_time.elapsed().as_secs();
+            send_log(format!(
+                "Recording stopped. Duration: {}s, Frames: {}",
+                duration, frame_count
+            ));
+
+            let _ = writer.release();
+
+            let sidecar = RecordingSidecar {
+                camera: &camera_info.name,
+                source: camera_info.source.source_type(),
+                file: filename.clone(),
+                started: started_at.to_rfc3339(),
+                duration_sec: duration,
+                frames: frame_count,
+                width: write_size.width,
+                height: write_size.height,
+                fps: record_fps,
+                metadata: &camera_info.metadata,
+            };
+            match sidecar.write(&output_path) {
+                Ok(path) => info!("Wrote sidecar: {}", path.display()),
+                Err(e) => warn!("Failed to write sidecar for {}: {}", filename, e),
+            }
+
+            send_msg(VideoMessage::RecordingStopped {
+                camera: camera_info.name.clone(),
+                duration_sec: duration,
+            });
+
+            if end != SegmentEnd::Limit || !config.auto_restart {
+                break;
+            }
+
+            // Pause briefly before the next segment to smooth the writer handoff
+            let pause = config.restart_pause(&mut restart_rng);
+            if !pause.is_zero() {
+                send_log(format!("Starting next segment in {} ms", pause.as_millis()));
+                if !Self::sleep_unless_stopped(pause, &stop_requested) {
+                    send_log("Stop signal received".to_string());
                     break;
                 }
             }
+        }
 
-            // Small delay to prevent CPU spinning
-            thread::sleep(Duration::from_millis(1));
+        let _ = camera.release();
+
+        *state.lock().unwrap() = RecordingState::Idle;
+    }
+
+    /// Open the camera source. Failures are retried with capped exponential
+    /// backoff up to `open_retries` times (forever with `reconnect_forever`)
+    /// until it opens or a stop is requested (returns Ok(None)); after that
+    /// the last failure is returned.
+    #[cfg(feature = "video")]
+    fn connect(
+        camera_info: &CameraInfo,
+        config: &VideoConfig,
+        stop_requested: &dyn Fn() -> bool,
+        send_log: &dyn Fn(String),
+    ) -> Result<Option<VideoCapture>, String> {
+        let mut attempt: u32 = 0;
+
+        loop {
+            attempt += 1;
+            let error = match Self::open_checked(camera_info, config) {
+                Ok(camera) => return Ok(Some(camera)),
+                Err(e) if !config.reconnect_forever && attempt > config.open_retries => {
+                    return Err(if attempt > 1 {
+                        format!("{} (gave up after {} attempts)", e, attempt)
+                    } else {
+                        e
+                    });
+                }
+                Err(e) => e,
+            };
+
+            let backoff = Duration::from_secs(
+                (1u64 << (attempt - 1).min(5)).min(RECONNECT_BACKOFF_MAX_SEC),
+            );
+            send_log(format!(
+                "{} (attempt {}), retrying in {}s",
+                error, attempt, backoff.as_secs()
+            ));
+
+            if !Self::sleep_unless_stopped(backoff, stop_requested) {
+                return Ok(None);
+            }
         }
+    }
 
-        // Cleanup
-        let duration = start_time.elapsed().as_secs();
-        send_log(format!(
-            "Recording stopped. Duration: {}s, Frames: {}",
-            duration, frame_count
-        ));
+    /// Re-open a stream that stopped delivering frames, waiting 1s, 2s, 4s...
+    /// (capped) before each attempt. Returns Ok(None) if a stop was requested,
+    /// or an error once `max_reconnect_attempts` is exhausted.
+    #[cfg(feature = "video")]
+    fn reconnect(
+        camera_info: &CameraInfo,
+        config: &VideoConfig,
+        stop_requested: &dyn Fn() -> bool,
+        send_log: &dyn Fn(String),
+    ) -> Result<Option<VideoCapture>, String> {
+        let mut attempt: u32 = 0;
 
-        let _ = writer.release();
-        let _ = camera.release();
+        loop {
+            attempt += 1;
+            let backoff = Duration::from_secs(
+                (1u64 << (attempt - 1).min(5)).min(RECONNECT_BACKOFF_MAX_SEC),
+            );
+            send_log(format!("Stream lost, reconnect attempt {} in {}s", attempt, backoff.as_secs()));
+
+            if !Self::sleep_unless_stopped(backoff, stop_requested) {
+                return Ok(None);
+            }
 
-        send_msg(VideoMessage::RecordingStopped {
-            camera: camera_info.name.clone(),
-            duration_sec: duration,
-        });
+            match Self::open_checked(camera_info, config) {
+                Ok(camera) => return Ok(Some(camera)),
+                Err(e) if !config.reconnect_forever && attempt >= config.max_reconnect_attempts => {
+                    return Err(format!("Stream lost; reconnect failed after {} attempts: {}", attempt, e));
+                }
+                Err(e) => send_log(format!("Reconnect attempt {} failed: {}", attempt, e)),
+            }
+        }
+    }
 
-        *state.lock().unwrap() = RecordingState::Idle;
+    /// Open the source, confirm it reports as opened and apply the buffer size
+    #[cfg(feature = "video")]
+    fn open_checked(camera_info: &CameraInfo, config: &VideoConfig) -> Result<VideoCapture, String> {
+        let mut camera = open_capture(&camera_info.source, config.hw_accel, camera_info.rtsp_transport)?;
+        match camera.is_opened() {
+            Ok(true) => {}
+            Ok(false) => return Err(camera_info.source.open_failure()),
+            Err(e) => return Err(format!("Error checking camera status: {}", e)),
+        }
+
+        if let Some(frames) = config.capture_buffer_size {
+            match camera.set(videoio::CAP_PROP_BUFFERSIZE, frames as f64) {
+                Ok(true) => info!("Capture buffer size set to {} frame(s)", frames),
+                Ok(false) => warn!("Capture backend ignored buffer size {} (not supported)", frames),
+                Err(e) => warn!("Failed to set capture buffer size: {}", e),
+            }
+        }
+
+        Ok(camera)
+    }
+
+    /// Sleep for `duration`, waking early if a stop is requested; false if stopped
+    #[cfg(feature = "video")]
+    fn sleep_unless_stopped(duration: Duration, stop_requested: &dyn Fn() -> bool) -> bool {
+        let deadline = Instant::now() + duration;
+        while Instant::now() < deadline {
+            if stop_requested() {
+                return false;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        true
+    }
+
+    /// Delete `camera_name`'s recordings and sidecars in `dir` (and up to `depth`
+    /// levels of subdirectories) last modified more than `hours` ago. Only files
+    /// matching `<name>_<YYYYMMDD>_...` are touched, so other cameras' files are
+    /// left alone; subdirectories emptied this way are removed. Returns the number
+    /// of videos removed.
+    #[cfg(feature = "video")]
+    fn sweep_old_segments(dir: &Path, depth: usize, camera_name: &str, hours: u64) -> usize {
+        let prefix = format!("{}_", sanitize_filename(camera_name));
+        let max_age = Duration::from_secs(hours * 3600);
+        let extensions = [VideoFormat::MP4, VideoFormat::AVI, VideoFormat::MKV].map(|f| f.extension().to_string());
+
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return 0;
+        };
+
+        let mut removed = 0;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if depth > 0 && entry.file_type().is_ok_and(|t| t.is_dir()) {
+                let swept = Self::sweep_old_segments(&path, depth - 1, camera_name, hours);
+                if swept > 0 && std::fs::remove_dir(&path).is_ok() {
+                    info!("Retention: removed empty directory {}", path.display());
+                }
+                removed += swept;
+                continue;
+            }
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+
+            let ours = name.strip_prefix(&prefix)
+                .is_some_and(|rest| rest.len() > 8 && rest.as_bytes()[..8].iter().all(u8::is_ascii_digit));
+            let is_video = path.extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| extensions.iter().any(|x| x == e));
+            if !ours || !is_video {
+                continue;
+            }
+
+            let expired = entry.metadata()
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|t| t.elapsed().ok())
+                .is_some_and(|age| age > max_age);
+            if !expired {
+                continue;
+            }
+
+            match std::fs::remove_file(&path) {
+                Ok(()) => {
+                    removed += 1;
+                    info!("Retention: deleted {}", path.display());
+                    let mut sidecar = path.into_os_string();
+                    sidecar.push(".json");
+                    let _ = std::fs::remove_file(sidecar);
+                }
+                Err(e) => warn!("Retention: failed to delete {}: {}", path.display(), e),
+            }
+        }
+
+        removed
+    }
+
+    /// Create a writer for one segment and make sure it actually opened
+    #[cfg(feature = "video")]
+    fn open_writer(path: &Path, fourcc: i32, size: Size, fps: f64) -> Result<VideoWriter, String> {
+        let writer = VideoWriter::new(path.to_str().unwrap(), fourcc, fps, size, true)
+            .map_err(|e| format!("Failed to create video writer: {}", e))?;
+
+        match writer.is_opened() {
+            Ok(true) => Ok(writer),
+            Ok(false) => Err(format!(
+                "Video writer failed to open (FourCC {} may not be supported by this OpenCV build)",
+                fourcc_to_string(fourcc)
+            )),
+            Err(e) => Err(format!("Error checking writer status: {}", e)),
+        }
+    }
+
+    /// Open the writer at the camera's size and codec; if that fails, try MJPG
+    /// (as .avi) at the same size, then each smaller fallback size with the
+    /// requested codec and with MJPG. Returns the writer with the path, FourCC
+    /// and frame size actually used, or the original error if nothing opens.
+    #[cfg(feature = "video")]
+    fn open_writer_with_fallback(
+        path: &Path,
+        fourcc: i32,
+        size: Size,
+        fps: f64,
+        send_log: &dyn Fn(String),
+    ) -> Result<(VideoWriter, PathBuf, i32, Size), String> {
+        let error = match Self::open_writer(path, fourcc, size, fps) {
+            Ok(writer) => return Ok((writer, path.to_path_buf(), fourcc, size)),
+            Err(e) => e,
+        };
+
+        let mjpg = VideoFormat::AVI.fourcc();
+        let sizes = std::iter::once(size).chain(
+            WRITER_FALLBACK_SCALES
+                .iter()
+                .map(|&factor| Size::new(
+                    (size.width as f64 * factor) as i32 & !1,
+                    (size.height as f64 * factor) as i32 & !1,
+                ))
+                .filter(|s| s.width > 0 && s.height > 0),
+        );
+        let mut candidates: Vec<(i32, Size)> = Vec::new();
+        for s in sizes {
+            if s != size {
+                candidates.push((fourcc, s));
+            }
+            if fourcc != mjpg {
+                candidates.push((mjpg, s));
+            }
+        }
+
+        // MJPG needs an .avi container; pick a name no other segment uses
+        let avi = VideoFormat::AVI.extension();
+        let avi_path = match (path.parent(), path.file_stem()) {
+            (Some(dir), Some(stem)) if path.extension().is_none_or(|e| e != avi) => {
+                dir.join(Self::unique_filename(dir, &stem.to_string_lossy(), avi))
+            }
+            _ => path.to_path_buf(),
+        };
+        // A rejected open can leave an empty file behind
+        let remove_empty = |keep: Option<&Path>| {
+            for tried in [path, avi_path.as_path()] {
+                if Some(tried) != keep && std::fs::metadata(tried).is_ok_and(|m| m.len() == 0) {
+                    let _ = std::fs::remove_file(tried);
+                }
+            }
+        };
+
+        warn!("{}", error);
+        for (candidate, candidate_size) in candidates {
+            let candidate_path = if candidate == mjpg && candidate != fourcc {
+                avi_path.clone()
+            } else {
+                path.to_path_buf()
+            };
+            if let Ok(writer) = Self::open_writer(&candidate_path, candidate, candidate_size, fps) {
+                remove_empty(Some(&candidate_path));
+                send_log(format!(
+                    "Writer fallback: {}x{} {} (requested {}x{} {})",
+                    candidate_size.width,
+                    candidate_size.height,
+                    fourcc_to_string(candidate),
+                    size.width,
+                    size.height,
+                    fourcc_to_string(fourcc)
+                ));
+                return Ok((writer, candidate_path, candidate, candidate_size));
+            }
+        }
+
+        remove_empty(None);
+        Err(format!("{}; fallback sizes and MJPG also failed", error))
+    }
+
+    /// Write `frame`, scaling it first if the writer was opened at another size
+    #[cfg(feature = "video")]
+    fn write_frame(writer: &mut VideoWriter, frame: &Mat, size: Size) -> CvResult<()> {
+        if frame.size()? == size {
+            return writer.write(frame);
+        }
+        let mut scaled = Mat::default();
+        imgproc::resize(frame, &mut scaled, size, 0.0, 0.0, imgproc::INTER_AREA)?;
+        writer.write(&scaled)
+    }
+
+    /// Write a motion snapshot to `<output_dir>/snapshots/<date>/`, naming it with
+    /// the timestamp and the bounding box of the moving region
+    #[cfg(feature = "video")]
+    fn save_motion_snapshot(
+        frame: &Mat,
+        camera_name: &str,
+        output_dir: &Path,
+        motion: &MotionResult,
+    ) -> Result<PathBuf, String> {
+        let now = Local::now();
+        let dir = output_dir.join("snapshots").join(now.format("%Y-%m-%d").to_string());
+        std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+        let r = motion.region;
+        let filename = format!(
+            "{}_{}_x{}y{}w{}h{}.jpg",
+            sanitize_filename(camera_name),
+            now.format("%Y%m%d_%H%M%S_%3f"),
+            r.x, r.y, r.width, r.height
+        );
+        let path = dir.join(filename);
+
+        match imgcodecs::imwrite(&path.to_string_lossy(), frame, &Vector::new()) {
+            Ok(true) => Ok(path),
+            Ok(false) => Err("imwrite returned false".to_string()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Answer pending `capture_snapshot` requests with `frame`
+    #[cfg(feature = "video")]
+    fn serve_snapshots(requests: &Receiver<SnapshotRequest>, frame: &Mat) {
+        let params = Vector::<i32>::from_slice(&[imgcodecs::IMWRITE_JPEG_QUALITY, SNAPSHOT_JPEG_QUALITY]);
+        while let Ok((path, reply)) = requests.try_recv() {
+            let result = match imgcodecs::imwrite(&path.to_string_lossy(), frame, &params) {
+                Ok(true) => Ok(()),
+                Ok(false) => Err(format!("Failed to encode snapshot {}", path.display())),
+                Err(e) => Err(format!("Failed to write snapshot {}: {}", path.display(), e)),
+            };
+            let _ = reply.send(result);
+        }
+    }
+
+    /// Append a copy of `frame` (with the recording overlay, so lead-up frames
+    /// carry their own timestamp) to the pre-record buffer, dropping the
+    /// oldest frames beyond `cap`
+    #[cfg(feature = "video")]
+    fn buffer_frame(buffer: &mut VecDeque<Mat>, cap: usize, frame: &Mat, camera: &str, config: &VideoConfig) {
+        let mut copy = match frame.try_clone() {
+            Ok(copy) => copy,
+            Err(e) => {
+                warn!("Failed to buffer pre-record frame: {}", e);
+                return;
+            }
+        };
+        if config.overlay_target.recording() {
+            if let Some(text) = config.overlay_text(camera, "REC") {
+                if let Err(e) = Self::draw_overlay(&mut copy, &text, config) {
+                    warn!("Failed to draw overlay: {}", e);
+                }
+            }
+        }
+        while buffer.len() >= cap {
+            buffer.pop_front();
+        }
+        buffer.push_back(copy);
+    }
+
+    /// Draw `text` in the configured corner. Positions are computed from the
+    /// frame itself, so resolution changes (and previews) are handled.
+    #[cfg(feature = "video")]
+    fn draw_overlay(frame: &mut Mat, text: &str, config: &VideoConfig) -> CvResult<()> {
+        let (cols, rows) = (frame.cols(), frame.rows());
+        let font = imgproc::FONT_HERSHEY_SIMPLEX;
+
+        let mut scale = config.overlay_font_scale;
+        let mut thickness = ((scale * 2.0).round() as i32).max(1);
+        let mut baseline = 0;
+        let mut size = imgproc::get_text_size(text, font, scale, thickness, &mut baseline)?;
+
+        // Shrink to fit small frames
+        let available = cols - 2 * OVERLAY_MARGIN_PX;
+        if size.width > available && available > 0 {
+            scale *= available as f64 / size.width as f64;
+            thickness = ((scale * 2.0).round() as i32).max(1);
+            size = imgproc::get_text_size(text, font, scale, thickness, &mut baseline)?;
+        }
+
+        let x = match config.overlay_corner {
+            OverlayCorner::TopLeft | OverlayCorner::BottomLeft => OVERLAY_MARGIN_PX,
+            OverlayCorner::TopRight | OverlayCorner::BottomRight => cols - size.width - OVERLAY_MARGIN_PX,
+        };
+        let y = match config.overlay_corner {
+            OverlayCorner::TopLeft | OverlayCorner::TopRight => OVERLAY_MARGIN_PX + size.height,
+            OverlayCorner::BottomLeft | OverlayCorner::BottomRight => rows - OVERLAY_MARGIN_PX - baseline,
+        };
+        let origin = core::Point::new(x.max(0), y.clamp(size.height, rows.max(size.height)));
+
+        // Dark outline under white text stays readable on any background
+        let black = core::Scalar::new(0.0, 0.0, 0.0, 0.0);
+        let white = core::Scalar::new(255.0, 255.0, 255.0, 0.0);
+        imgproc::put_text(frame, text, origin, font, scale, black, thickness + 2, imgproc::LINE_AA, false)?;
+        imgproc::put_text(frame, text, origin, font, scale, white, thickness, imgproc::LINE_AA, false)?;
+
+        Ok(())
+    }
+
+    /// Downscale a frame to `width` (keeping aspect ratio), draw `overlay` on
+    /// the small image if given, and encode it as JPEG
+    #[cfg(feature = "video")]
+    fn encode_preview(frame: &Mat, width: i32, overlay: Option<&str>, config: &VideoConfig) -> CvResult<Vec<u8>> {
+        let (cols, rows) = (frame.cols(), frame.rows());
+        let height = ((rows as f64 * width as f64 / cols.max(1) as f64).round() as i32).max(1);
+
+        let mut small = Mat::default();
+        imgproc::resize(frame, &mut small, Size::new(width, height), 0.0, 0.0, imgproc::INTER_AREA)?;
+        if let Some(text) = overlay {
+            Self::draw_overlay(&mut small, text, config)?;
+        }
+
+        let mut buf = Vector::<u8>::new();
+        let params = Vector::<i32>::from_slice(&[imgcodecs::IMWRITE_JPEG_QUALITY, PREVIEW_JPEG_QUALITY]);
+        imgcodecs::imencode(".jpg", &small, &mut buf, &params)?;
+
+        Ok(buf.to_vec())
     }
 }
 
 impl Drop for VideoRecorder {
     fn drop(&mut self) {
-        if self.is_recording() {
+        if self.is_recording() || self.is_armed() || self.is_paused() {
             let _ = self.stop_recording();
         }
     }
@@ -573,9 +2556,23 @@ impl Drop for VideoRecorder {
 // MULTI-CAMERA MANAGER
 // ============================================================================
 
+/// Scheduling state of a camera inside a `MultiCameraRecorder`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CameraSlot {
+    /// Actively capturing and encoding
+    Active,
+    /// Waiting for a free slot (max concurrent recorders reached)
+    Queued,
+    /// Not recording
+    Idle,
+}
+
 pub struct MultiCameraRecorder {
     recorders: Vec<VideoRecorder>,
     tx_to_gui: Option<Sender<VideoMessage>>,
+    max_concurrent: Option<usize>,
+    queue: VecDeque<usize>,
+    stop_flag: Option<Arc<AtomicBool>>,
 }
 
 impl MultiCameraRecorder {
@@ -583,6 +2580,9 @@ impl MultiCameraRecorder {
         Self {
             recorders: Vec::new(),
             tx_to_gui: None,
+            max_concurrent: None,
+            queue: VecDeque::new(),
+            stop_flag: None,
         }
     }
 
@@ -591,22 +2591,66 @@ impl MultiCameraRecorder {
         self
     }
 
+    /// Share one stop flag with every camera added afterwards
+    pub fn with_stop_flag(mut self, flag: Arc<AtomicBool>) -> Self {
+        self.stop_flag = Some(flag);
+        self
+    }
+
+    /// Limit how many cameras record at once; extra starts are queued
+    pub fn with_max_concurrent(mut self, max: usize) -> Self {
+        self.max_concurrent = Some(max.max(1));
+        self
+    }
+
     pub fn add_camera(&mut self, camera_info: CameraInfo, config: VideoConfig) {
         let mut recorder = VideoRecorder::new(camera_info, config);
         
         if let Some(tx) = &self.tx_to_gui {
             recorder = recorder.with_gui_sender(tx.clone());
         }
+
+        if let Some(flag) = &self.stop_flag {
+            recorder = recorder.with_stop_flag(flag.clone());
+        }
         
         self.recorders.push(recorder);
     }
 
+    /// Add a camera that records its main and sub-stream concurrently, each with
+    /// its own config (e.g. main for event clips, sub for a long-retention archive).
+    /// Use [`CameraInfo::sub_stream`] to build `sub`.
+    pub fn add_camera_with_sub_stream(
+        &mut self,
+        main: CameraInfo,
+        main_config: VideoConfig,
+        sub: CameraInfo,
+        sub_config: VideoConfig,
+    ) {
+        self.add_camera(main, main_config);
+        self.add_camera(sub, sub_config);
+    }
+
     pub fn start_all(&mut self) -> Result<(), String> {
         let mut errors = Vec::new();
         
-        for recorder in &mut self.recorders {
-            if let Err(e) = recorder.start_recording() {
-                errors.push(e);
+        for idx in 0..self.recorders.len() {
+            if self.recorders[idx].is_active() || self.queue.contains(&idx) {
+                continue;
+            }
+
+            if self.has_free_slot() {
+                if let Err(e) = self.recorders[idx].start_recording() {
+                    errors.push(e);
+                }
+            } else {
+                info!("Queued camera: {}", self.recorders[idx].camera_name());
+                self.send_message(VideoMessage::Log(format!(
+                    "Queued {} (max {} concurrent recorders)",
+                    self.recorders[idx].camera_name(),
+                    self.max_concurrent.unwrap_or(0)
+                )));
+                self.queue.push_back(idx);
             }
         }
 
@@ -617,15 +2661,68 @@ impl MultiCameraRecorder {
         }
     }
 
+    /// Start queued cameras as slots free up. Call periodically (e.g. from the
+    /// GUI update loop) while a concurrency limit is set.
+    pub fn process_queue(&mut self) {
+        while self.has_free_slot() {
+            let Some(idx) = self.queue.pop_front() else {
+                break;
+            };
+
+            let recorder = &mut self.recorders[idx];
+            info!("Starting queued camera: {}", recorder.camera_name());
+            if let Err(e) = recorder.start_recording() {
+                let msg = format!("Failed to start queued camera {}: {}", recorder.camera_name(), e);
+                error!("{}", msg);
+                self.send_message(VideoMessage::Error(msg));
+            }
+        }
+    }
+
     pub fn stop_all(&mut self) -> Result<(), String> {
+        self.queue.clear();
         for recorder in &mut self.recorders {
             let _ = recorder.stop_recording();
         }
         Ok(())
     }
 
+    /// Cameras holding a concurrency slot (recording, paused or armed)
     pub fn recording_count(&self) -> usize {
-        self.recorders.iter().filter(|r| r.is_recording()).count()
+        self.recorders.iter().filter(|r| r.is_active()).count()
+    }
+
+    pub fn queued_count(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Per-camera scheduling state, in the order cameras were added
+    pub fn camera_states(&self) -> Vec<(String, CameraSlot)> {
+        self.recorders
+            .iter()
+            .enumerate()
+            .map(|(idx, r)| {
+                let slot = if r.is_active() {
+                    CameraSlot::Active
+                } else if self.queue.contains(&idx) {
+                    CameraSlot::Queued
+                } else {
+                    CameraSlot::Idle
+                };
+                (r.camera_name().to_string(), slot)
+            })
+            .collect()
+    }
+
+    fn has_free_slot(&self) -> bool {
+        self.max_concurrent
+            .is_none_or(|max| self.recording_count() < max)
+    }
+
+    fn send_message(&self, msg: VideoMessage) {
+        if let Some(tx) = &self.tx_to_gui {
+            let _ = tx.send(msg);
+        }
     }
 }
 
@@ -634,3 +2731,340 @@ impl Default for MultiCameraRecorder {
         Self::new()
     }
 }
+
+// ============================================================================
+// ONVIF DISCOVERY
+// ============================================================================
+
+/// WS-Discovery multicast group and port used by ONVIF devices
+const WS_DISCOVERY_ADDR: &str = "239.255.255.250:3702";
+
+/// An ONVIF device that answered a WS-Discovery probe
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscoveredCamera {
+    /// Address the response came from
+    pub address: std::net::IpAddr,
+    /// Device service URLs (XAddrs), e.g. `http://192.168.1.20/onvif/device_service`
+    pub service_urls: Vec<String>,
+    /// ONVIF scopes (`onvif://www.onvif.org/name/...`, `.../hardware/...`)
+    pub scopes: Vec<String>,
+}
+
+impl DiscoveredCamera {
+    /// Device name from the `name` scope, if advertised
+    pub fn name(&self) -> Option<String> {
+        self.scope_value("name")
+    }
+
+    /// Hardware/model from the `hardware` scope, if advertised
+    pub fn hardware(&self) -> Option<String> {
+        self.scope_value("hardware")
+    }
+
+    /// Likely RTSP base URL (port 554). The exact stream path is vendor
+    /// specific and is normally queried from the device service (GetStreamUri).
+    pub fn rtsp_candidate(&self) -> String {
+        format!("rtsp://{}:554/", self.address)
+    }
+
+    fn scope_value(&self, key: &str) -> Option<String> {
+        let prefix = format!("onvif://www.onvif.org/{}/", key);
+        self.scopes
+            .iter()
+            .find_map(|s| s.strip_prefix(&prefix))
+            .map(|v| v.replace("%20", " "))
+    }
+}
+
+/// Multicast an ONVIF WS-Discovery probe on the LAN and collect the devices
+/// that answer within `timeout`. Network errors are logged and yield an
+/// empty (or partial) list.
+pub fn discover_onvif(timeout: Duration) -> Vec<DiscoveredCamera> {
+    use std::net::UdpSocket;
+
+    let socket = match UdpSocket::bind("0.0.0.0:0") {
+        Ok(s) => s,
+        Err(e) => {
+            warn!("ONVIF discovery: failed to open socket: {}", e);
+            return Vec::new();
+        }
+    };
+    let _ = socket.set_multicast_ttl_v4(2);
+
+    let probe = ws_discovery_probe();
+    if let Err(e) = socket.send_to(probe.as_bytes(), WS_DISCOVERY_ADDR) {
+        warn!("ONVIF discovery: failed to send probe: {}", e);
+        return Vec::new();
+    }
+
+    let deadline = Instant::now() + timeout;
+    let mut found: Vec<DiscoveredCamera> = Vec::new();
+    let mut buf = [0u8; 65_535];
+
+    while let Some(remaining) = deadline.checked_duration_since(Instant::now()).filter(|d| !d.is_zero()) {
+        let _ = socket.set_read_timeout(Some(remaining));
+        let (len, from) = match socket.recv_from(&mut buf) {
+            Ok(r) => r,
+            Err(_) => break,
+        };
+
+        let response = String::from_utf8_lossy(&buf[..len]);
+        let Some(camera) = parse_probe_match(&response, from.ip()) else {
+            continue;
+        };
+        if found.iter().any(|c| c.address == camera.address) {
+            continue;
+        }
+
+        info!(
+            "ONVIF device at {} ({})",
+            camera.address,
+            camera.name().unwrap_or_else(|| "unnamed".to_string())
+        );
+        found.push(camera);
+    }
+
+    info!("ONVIF discovery found {} device(s)", found.len());
+    found
+}
+
+/// The device described by a WS-Discovery ProbeMatch response from `address`;
+/// None for any other message
+fn parse_probe_match(response: &str, address: std::net::IpAddr) -> Option<DiscoveredCamera> {
+    if !response.contains("ProbeMatch") {
+        return None;
+    }
+    Some(DiscoveredCamera {
+        address,
+        service_urls: xml_element_words(response, "XAddrs"),
+        scopes: xml_element_words(response, "Scopes"),
+    })
+}
+
+/// SOAP Probe for ONVIF network video transmitters
+fn ws_discovery_probe() -> String {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos());
+    let message_id = format!(
+        "uuid:{:08x}-{:04x}-4{:03x}-8{:03x}-{:012x}",
+        (nanos >> 32) as u32,
+        (nanos >> 16) as u16,
+        (nanos & 0xfff) as u16,
+        std::process::id() & 0xfff,
+        nanos & 0xffff_ffff_ffff
+    );
+
+    format!(
+        concat!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+            r#"<e:Envelope xmlns:e="http://www.w3.org/2003/05/soap-envelope" "#,
+            r#"xmlns:w="http://schemas.xmlsoap.org/ws/2004/08/addressing" "#,
+            r#"xmlns:d="http://schemas.xmlsoap.org/ws/2005/04/discovery" "#,
+            r#"xmlns:dn="http://www.onvif.org/ver10/network/wsdl">"#,
+            r#"<e:Header><w:MessageID>{}</w:MessageID>"#,
+            r#"<w:To e:mustUnderstand="true">urn:schemas-xmlsoap-org:ws:2005:04:discovery</w:To>"#,
+            r#"<w:Action e:mustUnderstand="true">http://schemas.xmlsoap.org/ws/2005/04/discovery/Probe</w:Action>"#,
+            r#"</e:Header><e:Body><d:Probe><d:Types>dn:NetworkVideoTransmitter</d:Types></d:Probe></e:Body>"#,
+            r#"</e:Envelope>"#,
+        ),
+        message_id
+    )
+}
+
+/// Whitespace-separated contents of the first `<name>` / `<prefix:name ...>` element
+fn xml_element_words(xml: &str, name: &str) -> Vec<String> {
+    let open = xml.match_indices('<').map(|(i, _)| i).find(|&i| {
+        let tag = &xml[i + 1..];
+        let local = tag.split_once(':').filter(|(p, _)| !p.contains(['>', ' ', '/'])).map_or(tag, |(_, l)| l);
+        local.starts_with(name) && local[name.len()..].starts_with(['>', ' '])
+    });
+    let Some(open) = open else {
+        return Vec::new();
+    };
+
+    let start = xml[open..].find('>').map_or(xml.len(), |i| open + i + 1);
+    let end = xml[start..].find("</").map_or(xml.len(), |i| start + i);
+
+    xml[start..end].split_whitespace().map(str::to_string).collect()
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROBE_MATCH: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<SOAP-ENV:Envelope xmlns:SOAP-ENV="http://www.w3.org/2003/05/soap-envelope" xmlns:wsa="http://schemas.xmlsoap.org/ws/2004/08/addressing" xmlns:d="http://schemas.xmlsoap.org/ws/2005/04/discovery" xmlns:dn="http://www.onvif.org/ver10/network/wsdl">
+<SOAP-ENV:Header>
+<wsa:Action>http://schemas.xmlsoap.org/ws/2005/04/discovery/ProbeMatches</wsa:Action>
+</SOAP-ENV:Header>
+<SOAP-ENV:Body>
+<d:ProbeMatches>
+<d:ProbeMatch>
+<wsa:EndpointReference><wsa:Address>urn:uuid:2419d68a-2dd2-21b2-a205-ec71db000001</wsa:Address></wsa:EndpointReference>
+<d:Types>dn:NetworkVideoTransmitter</d:Types>
+<d:Scopes>onvif://www.onvif.org/type/video_encoder onvif://www.onvif.org/name/Front%20Door
+ onvif://www.onvif.org/hardware/IPC-HDW2431T</d:Scopes>
+<d:XAddrs>http://192.168.1.20/onvif/device_service http://[fe80::1]/onvif/device_service</d:XAddrs>
+<d:MetadataVersion>1</d:MetadataVersion>
+</d:ProbeMatch>
+</d:ProbeMatches>
+</SOAP-ENV:Body>
+</SOAP-ENV:Envelope>"#;
+
+    fn address() -> std::net::IpAddr {
+        "192.168.1.20".parse().unwrap()
+    }
+
+    #[test]
+    fn xml_element_words_reads_namespaced_tags() {
+        assert_eq!(
+            xml_element_words(PROBE_MATCH, "XAddrs"),
+            ["http://192.168.1.20/onvif/device_service", "http://[fe80::1]/onvif/device_service"]
+        );
+        assert_eq!(xml_element_words(PROBE_MATCH, "Types"), ["dn:NetworkVideoTransmitter"]);
+    }
+
+    #[test]
+    fn xml_element_words_handles_attributes_and_missing_elements() {
+        let xml = r#"<Scopes MatchBy="rfc3986">a b</Scopes><ScopesExtra>c</ScopesExtra>"#;
+        assert_eq!(xml_element_words(xml, "Scopes"), ["a", "b"]);
+        assert!(xml_element_words(xml, "XAddrs").is_empty());
+        assert!(xml_element_words("<d:XAddrs/>", "XAddrs").is_empty());
+    }
+
+    #[test]
+    fn parse_probe_match_reads_device() {
+        let camera = parse_probe_match(PROBE_MATCH, address()).unwrap();
+        assert_eq!(camera.service_urls.len(), 2);
+        assert_eq!(camera.scopes.len(), 3);
+        assert_eq!(camera.name().as_deref(), Some("Front Door"));
+        assert_eq!(camera.hardware().as_deref(), Some("IPC-HDW2431T"));
+        assert_eq!(camera.rtsp_candidate(), "rtsp://192.168.1.20:554/");
+    }
+
+    fn at(year: i32, month: u32, day: u32) -> chrono::DateTime<Local> {
+        use chrono::TimeZone;
+        Local.with_ymd_and_hms(year, month, day, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn segment_dir_without_template_is_output_dir() {
+        let config = VideoConfig::new().with_output_dir("out");
+        assert_eq!(config.segment_dir("Front Door", at(2024, 3, 5)), PathBuf::from("out"));
+    }
+
+    #[test]
+    fn segment_dir_expands_placeholders() {
+        let config = VideoConfig::new().with_output_dir("out").with_subdir_template("{camera}/{date}");
+        assert_eq!(
+            config.segment_dir("Front Door", at(2024, 3, 5)),
+            Path::new("out").join("Front_Door").join("2024-03-05")
+        );
+
+        let config = config.with_subdir_template("{year}/{month}/{day}");
+        assert_eq!(
+            config.segment_dir("Front Door", at(2024, 3, 5)),
+            Path::new("out").join("2024").join("03").join("05")
+        );
+    }
+
+    #[test]
+    fn segment_dir_stays_inside_output_dir() {
+        let config = VideoConfig::new().with_output_dir("out").with_subdir_template("../../etc");
+        assert_eq!(config.segment_dir("cam", at(2024, 3, 5)), Path::new("out").join("etc"));
+
+        let config = config.with_subdir_template("/abs/./{camera}");
+        assert_eq!(config.segment_dir("cam", at(2024, 3, 5)), Path::new("out").join("abs").join("cam"));
+
+        // A hostile camera name can't add path components either
+        let config = config.with_subdir_template("{camera}");
+        let dir = config.segment_dir("../../etc", at(2024, 3, 5));
+        assert!(dir.starts_with("out"));
+        assert_eq!(dir.components().count(), 2);
+    }
+
+    #[test]
+    fn parse_probe_match_ignores_other_messages() {
+        let probe = ws_discovery_probe();
+        assert!(parse_probe_match(&probe, address()).is_none());
+    }
+
+    #[test]
+    fn capture_event_failure_leaves_recorder_disarmed() {
+        let dir = std::env::temp_dir().join("seccamcloud_capture_event_test");
+        let camera = CameraInfo::new("Missing", CameraSource::VideoFile(dir.join("missing.mp4").display().to_string()));
+        let config = VideoConfig::new().with_output_dir(&dir).with_first_frame_timeout(1);
+        let mut recorder = VideoRecorder::new(camera, config);
+
+        assert!(recorder.capture_event(1, 1).is_err());
+        assert!(!recorder.is_armed());
+        assert!(!recorder.is_recording());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn auto_select_follows_source_codec() {
+        let rtsp = CameraSource::RtspStream("rtsp://cam".to_string());
+        let webcam = CameraSource::Webcam(0);
+        assert_eq!(VideoFormat::auto_select(&webcam, "h264").0, VideoFormat::MKV);
+        assert_eq!(VideoFormat::auto_select(&webcam, "HEVC").0, VideoFormat::MKV);
+        assert_eq!(VideoFormat::auto_select(&rtsp, "MJPG").0, VideoFormat::AVI);
+    }
+
+    #[test]
+    fn auto_select_falls_back_per_source() {
+        let cases = [
+            (CameraSource::RtspStream("rtsp://cam".to_string()), VideoFormat::MKV),
+            (CameraSource::HttpStream("http://cam".to_string()), VideoFormat::AVI),
+            (CameraSource::Webcam(0), VideoFormat::MP4),
+            (CameraSource::VideoFile("clip.avi".to_string()), VideoFormat::MP4),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(VideoFormat::auto_select(&source, "").0, expected, "{:?}", source);
+            assert_eq!(VideoFormat::auto_select(&source, "YUYV").0, expected, "{:?}", source);
+        }
+    }
+
+    #[test]
+    fn with_fourcc_accepts_four_ascii_characters() {
+        assert_eq!(VideoConfig::new().with_fourcc("H264").unwrap().fourcc, Some(*b"H264"));
+        assert_eq!(VideoConfig::new().with_fourcc("mp4v").unwrap().fourcc, Some(*b"mp4v"));
+        assert_eq!(VideoConfig::new().with_fourcc("Y8  ").unwrap().fourcc, Some(*b"Y8  "));
+    }
+
+    #[test]
+    fn with_fourcc_rejects_other_lengths_and_characters() {
+        for code in ["", "H26", "H2645", "H\t64", "H26é"] {
+            assert!(VideoConfig::new().with_fourcc(code).is_err(), "{:?}", code);
+        }
+    }
+
+    #[cfg(feature = "video")]
+    #[test]
+    fn restart_pause_is_seeded_and_bounded() {
+        let config = VideoConfig::new().with_restart_delay(500, 200);
+        let pauses = |seed| {
+            let mut rng = SimpleRng::new(seed);
+            (0..200).map(|_| config.restart_pause(&mut rng)).collect::<Vec<_>>()
+        };
+
+        let first = pauses(7);
+        assert_eq!(first, pauses(7));
+        assert!(first.iter().all(|p| (500..=700).contains(&p.as_millis())));
+        assert!(first.iter().any(|p| p.as_millis() != first[0].as_millis()));
+        assert_eq!(VideoConfig::new().with_restart_delay(500, 0).restart_pause(&mut SimpleRng::new(1)).as_millis(), 500);
+    }
+
+    #[test]
+    fn camera_source_parse_picks_kind_from_location() {
+        assert!(matches!(CameraSource::parse("0"), CameraSource::Webcam(0)));
+        assert!(matches!(CameraSource::parse("RTSP://cam/stream"), CameraSource::RtspStream(url) if url == "RTSP://cam/stream"));
+        assert!(matches!(CameraSource::parse("https://cam/mjpg"), CameraSource::HttpStream(_)));
+        assert!(matches!(CameraSource::parse("clips/door.mp4"), CameraSource::VideoFile(_)));
+    }
+}
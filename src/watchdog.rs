@@ -63,3 +63,13 @@ impl WatchdogTimer {
         *guard = None;
     }
 }
+
+impl Drop for WatchdogTimer {
+    fn drop(&mut self) {
+        // Stop watching and wait for the monitor thread so it never outlives its owner
+        self.cancel();
+        if let Some(handle) = self._thread.take() {
+            let _ = handle.join();
+        }
+    }
+}